@@ -1,6 +1,19 @@
+use std::time::Duration;
+
 use pareg::{Pareg, Result, check::InRangeI, has_any_key};
 
-use super::{ColorMode, OutputType, print_help, print_version};
+use crate::config::{Benchmark, Config};
+
+use super::{
+    ColorMode, CpuList, DurationArg, EnvOp, ExpectExit, HistoryCmd,
+    OutputFormat, OutputType, Parameter, Percent, RunCmd, RunOrder,
+    SchedPolicy, print_help, print_version,
+};
+
+#[cfg(target_os = "windows")]
+pub const DEFAULT_SHELL: &str = "cmd";
+#[cfg(not(target_os = "windows"))]
+pub const DEFAULT_SHELL: &str = "sh";
 
 #[derive(Debug, Default)]
 pub struct Args {
@@ -12,13 +25,139 @@ pub struct Args {
     pub capture_stdout: bool,
     pub capture_stderr: bool,
     pub repeat: usize,
+    pub warmup: usize,
+    pub shell: Option<String>,
+    pub calibrate_shell: bool,
+    pub format: OutputFormat,
+    pub parameter: Option<Parameter>,
+    pub setup: Option<String>,
+    pub prepare: Option<String>,
+    pub cleanup: Option<String>,
+    pub input: Option<String>,
+    pub no_stdin: bool,
+    pub stdout_to: Option<String>,
+    pub stderr_to: Option<String>,
+    pub only_failed_output: bool,
+    pub check_output: bool,
+    pub check_stderr: bool,
+    pub expect_output: Option<String>,
+    pub expect_exit: ExpectExit,
+    pub stop_on_failure: bool,
+    pub env: Vec<EnvOp>,
+    pub cwd: Option<String>,
+    pub tmpdir_per_run: bool,
+    pub cpus: Option<Vec<usize>>,
+    pub nice: Option<i32>,
+    pub sched: Option<SchedPolicy>,
+    pub order: RunOrder,
+    pub seed: Option<u64>,
+    pub min_runs: Option<usize>,
+    pub max_runs: Option<usize>,
+    pub target_ci: Option<f64>,
+    pub max_time: Option<Duration>,
+    pub confidence: Option<f64>,
+    pub save_samples: Option<String>,
+    pub compare: Option<String>,
+    pub histogram: bool,
+    pub no_progress: bool,
+    pub tui: bool,
+    pub plot: Option<String>,
+    pub html: Option<String>,
+    pub verbose: bool,
+    pub strict: bool,
+    pub record: Option<String>,
+    pub history: Option<HistoryCmd>,
+    pub run: Option<RunCmd>,
+    pub config: Config,
+    /// Arguments given on the command line.
+    cmdline: Vec<String>,
+    /// Step of parameter scan. It is applied after all the flags are parsed,
+    /// so that it may be given separately from the scan (e.g. in
+    /// configuration file).
+    step: Option<i64>,
 }
 
 impl Args {
-    pub fn parse(mut args: Pareg) -> Result<Self> {
+    /// Parse the command line arguments. Flags from the configuration files
+    /// are used as defaults.
+    pub fn parse(mut args: Pareg) -> crate::err::Result<Self> {
+        let config = match Config::load() {
+            Ok(c) => c,
+            // Help and version must work even with invalid configuration.
+            Err(e) => {
+                let mut res = Args::default();
+                let mut args = Pareg::new(args.remaining().to_vec());
+                res.parse_subcommand(&mut args)?;
+                res.parse_flags(args)?;
+                return if res.helped { Ok(res) } else { Err(e) };
+            }
+        };
+        let mut res = Args::default();
+        res.parse_flags(Pareg::new(config.defaults.clone()))?;
+        res.parse_subcommand(&mut args)?;
+        res.cmdline = args.remaining().to_vec();
+        res.parse_flags(args)?;
+        res.apply_step();
+        res.config = config;
+        Ok(res)
+    }
+
+    /// Get the arguments for running the given benchmark. Flags on the
+    /// command line take precedence over the flags of the benchmark and
+    /// those take precedence over the defaults.
+    pub fn benchmark(&self, bench: &Benchmark) -> Result<Self> {
         let mut res = Args::default();
+        res.parse_flags(Pareg::new(self.config.defaults.clone()))?;
+        res.parse_flags(Pareg::new(bench.flags.clone()))?;
+        res.parse_flags(Pareg::new(self.cmdline.clone()))?;
+        res.apply_step();
+        res.run = None;
+        res.program = Some(bench.program.clone());
+        res.args = bench.args.clone();
+        if res.record.as_deref() == Some("") {
+            res.record = Some(bench.name.clone());
+        }
+        Ok(res)
+    }
+
+    /// Parse the subcommand and its arguments. Subcommand is recognized only
+    /// as the first argument so that it doesn't hide program with the same
+    /// name.
+    fn parse_subcommand(&mut self, args: &mut Pareg) -> Result<()> {
+        match args.peek() {
+            Some("history") => {
+                args.next();
+                self.history = Some(HistoryCmd::parse(args)?);
+            }
+            Some("trend") => {
+                args.next();
+                self.history = Some(HistoryCmd::Trend {
+                    name: args.next_arg()?,
+                });
+            }
+            Some("run") => {
+                args.next();
+                self.run = Some(RunCmd::parse(args)?);
+            }
+            Some("suite") => {
+                args.next();
+                self.run = Some(RunCmd::Suite(args.next_arg()?));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn parse_flags(&mut self, mut args: Pareg) -> Result<()> {
+        let res = self;
 
         while let Some(arg) = args.next() {
+            if let Some(flag) =
+                arg.strip_prefix("--no-").and_then(|f| res.bool_flag(f))
+            {
+                *flag = false;
+                continue;
+            }
             match arg {
                 "-h" | "--help" | "-?" => {
                     res.helped = true;
@@ -51,6 +190,136 @@ impl Args {
                         InRangeI<_, 1, { usize::MAX as i128 + 1 }>
                     >()?.0;
                 }
+                "--warmup" => res.warmup = args.next_arg()?,
+                v if has_any_key!(v, '=', "-s", "--shell") => {
+                    res.shell = Some(
+                        args.cur_mval('=')?
+                            .unwrap_or_else(|| DEFAULT_SHELL.to_string()),
+                    );
+                }
+                "--calibrate-shell" => res.calibrate_shell = true,
+                v if has_any_key!(v, '=', "-f", "--format") => {
+                    res.format = args.cur_val_or_next('=')?;
+                }
+                "--parameter-scan" => {
+                    let name = args.next_arg()?;
+                    let start = args.next_arg()?;
+                    let end = args.next_arg()?;
+                    res.parameter = Some(Parameter::Scan {
+                        name,
+                        start,
+                        end,
+                        step: 1,
+                    });
+                }
+                "--step" => {
+                    res.step = Some(
+                        args.next_arg::<
+                            InRangeI<i64, 1, { i64::MAX as i128 + 1 }>
+                        >()?.0,
+                    );
+                }
+                "--parameter-list" => {
+                    let name = args.next_arg()?;
+                    let values = args
+                        .next_arg::<&str>()?
+                        .split(',')
+                        .map(str::to_string)
+                        .collect();
+                    res.parameter = Some(Parameter::List { name, values });
+                }
+                "--setup" => res.setup = Some(args.next_arg()?),
+                "--prepare" => res.prepare = Some(args.next_arg()?),
+                "--cleanup" => res.cleanup = Some(args.next_arg()?),
+                "-i" | "--input" => res.input = Some(args.next_arg()?),
+                "--no-stdin" => res.no_stdin = true,
+                "--stdin" => res.no_stdin = false,
+                "--stdout-to" => res.stdout_to = Some(args.next_arg()?),
+                "--stderr-to" => res.stderr_to = Some(args.next_arg()?),
+                "--only-failed-output" => res.only_failed_output = true,
+                "--check-output" => res.check_output = true,
+                "--check-stderr" => {
+                    res.check_output = true;
+                    res.check_stderr = true;
+                }
+                "--expect-output" => {
+                    res.check_output = true;
+                    res.expect_output = Some(args.next_arg()?);
+                }
+                "--expect-exit" => res.expect_exit = args.next_arg()?,
+                "--stop-on-failure" => res.stop_on_failure = true,
+                "--ignore-failure" => {
+                    res.expect_exit = ExpectExit::Any;
+                    res.stop_on_failure = false;
+                }
+                "-e" | "--env" => {
+                    let (k, v) = args.next_key_val('=')?;
+                    res.env.push(EnvOp::Set(k, v));
+                }
+                "--unset-env" => {
+                    res.env.push(EnvOp::Unset(args.next_arg()?));
+                }
+                "--clear-env" => res.env.push(EnvOp::Clear),
+                "--env-file" => res.env.push(EnvOp::File(args.next_arg()?)),
+                "--cwd" => res.cwd = Some(args.next_arg()?),
+                "--tmpdir-per-run" => res.tmpdir_per_run = true,
+                "--cpus" => {
+                    res.cpus = Some(args.next_arg::<CpuList>()?.0);
+                }
+                "--nice" => {
+                    res.nice =
+                        Some(args.next_arg::<InRangeI<i32, -20, 20>>()?.0);
+                }
+                "--sched" => res.sched = Some(args.next_arg()?),
+                v if has_any_key!(v, '=', "--order") => {
+                    res.order = args.cur_val_or_next('=')?;
+                }
+                "--seed" => res.seed = Some(args.next_arg()?),
+                "--min-runs" => {
+                    res.min_runs = Some(
+                        args.next_arg::<
+                            InRangeI<_, 1, { usize::MAX as i128 + 1 }>
+                        >()?.0,
+                    );
+                }
+                "--max-runs" => {
+                    res.max_runs = Some(
+                        args.next_arg::<
+                            InRangeI<_, 1, { usize::MAX as i128 + 1 }>
+                        >()?.0,
+                    );
+                }
+                "--target-ci" => {
+                    res.target_ci = Some(args.next_arg::<Percent>()?.0);
+                }
+                "--confidence" => {
+                    let conf = args.next_arg::<Percent>()?.0;
+                    if conf >= 1. {
+                        return args
+                            .err_invalid()
+                            .hint("Confidence must be less than `100%`.")
+                            .err();
+                    }
+                    res.confidence = Some(conf);
+                }
+                "--save-samples" => {
+                    res.save_samples = Some(args.next_arg()?);
+                }
+                "--compare" => res.compare = Some(args.next_arg()?),
+                "--histogram" => res.histogram = true,
+                "--no-progress" => res.no_progress = true,
+                "--progress" => res.no_progress = false,
+                "--tui" => res.tui = true,
+                "--plot" => res.plot = Some(args.next_arg()?),
+                "--html" => res.html = Some(args.next_arg()?),
+                "-v" | "--verbose" => res.verbose = true,
+                "--strict" => res.strict = true,
+                v if has_any_key!(v, '=', "--record") => {
+                    res.record = Some(args.cur_mval('=')?.unwrap_or_default());
+                }
+                "--max-time" => {
+                    res.max_time = Some(args.next_arg::<DurationArg>()?.0);
+                }
                 "--" => {
                     res.program = args.next().map(str::to_string);
                     res.args.extend(
@@ -74,6 +343,48 @@ impl Args {
             }
         }
 
-        Ok(res)
+        Ok(())
+    }
+
+    /// Set the step of parameter scan if it was given.
+    fn apply_step(&mut self) {
+        if let (Some(s), Some(Parameter::Scan { step, .. })) =
+            (self.step, &mut self.parameter)
+        {
+            *step = s;
+        }
+    }
+
+    /// Get the field of boolean flag with the given long name. These flags
+    /// can be disabled with `--no-<name>`.
+    fn bool_flag(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "capture-stdout" => Some(&mut self.capture_stdout),
+            "capture-stderr" => Some(&mut self.capture_stderr),
+            "calibrate-shell" => Some(&mut self.calibrate_shell),
+            "only-failed-output" => Some(&mut self.only_failed_output),
+            "check-output" => Some(&mut self.check_output),
+            "check-stderr" => Some(&mut self.check_stderr),
+            "stop-on-failure" => Some(&mut self.stop_on_failure),
+            "tmpdir-per-run" => Some(&mut self.tmpdir_per_run),
+            "histogram" => Some(&mut self.histogram),
+            "tui" => Some(&mut self.tui),
+            "verbose" => Some(&mut self.verbose),
+            "strict" => Some(&mut self.strict),
+            _ => None,
+        }
+    }
+
+    /// Checks whether the number of runs is chosen adaptively.
+    pub fn is_adaptive(&self) -> bool {
+        self.min_runs.is_some()
+            || self.max_runs.is_some()
+            || self.target_ci.is_some()
+            || self.max_time.is_some()
+    }
+
+    /// Get the shell used to run the program and other commands.
+    pub fn shell_name(&self) -> &str {
+        self.shell.as_deref().unwrap_or(DEFAULT_SHELL)
     }
 }
@@ -0,0 +1,40 @@
+use std::str::FromStr;
+
+use pareg::{ArgError, FromArgStr};
+
+/// List of CPU indexes. Parsed from comma separated list of indexes or
+/// ranges (e.g. `0,2-4`).
+#[derive(Clone, Debug, Default)]
+pub struct CpuList(pub Vec<usize>);
+
+impl FromStr for CpuList {
+    type Err = ArgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || {
+            ArgError::invalid_value(
+                "Invalid CPU list. Expected list of CPU indexes or ranges \
+                (e.g. `0,2-4`).",
+                s,
+            )
+        };
+        let parse = |v: &str| v.trim().parse::<usize>().map_err(|_| err());
+
+        let mut res = vec![];
+        for p in s.split(',') {
+            if let Some((a, b)) = p.split_once('-') {
+                let (a, b) = (parse(a)?, parse(b)?);
+                if a > b {
+                    return Err(err());
+                }
+                res.extend(a..=b);
+            } else {
+                res.push(parse(p)?);
+            }
+        }
+
+        Ok(Self(res))
+    }
+}
+
+impl FromArgStr for CpuList {}
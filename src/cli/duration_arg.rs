@@ -0,0 +1,45 @@
+use std::{str::FromStr, time::Duration};
+
+use pareg::{ArgError, FromArgStr};
+
+/// Duration given as number with optional unit (`ns`, `us`, `ms`, `s`, `m`,
+/// `h`). Number without unit is in seconds.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DurationArg(pub Duration);
+
+impl FromStr for DurationArg {
+    type Err = ArgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const UNITS: &[(&str, f64)] = &[
+            ("ns", 1e-9),
+            ("us", 1e-6),
+            ("μs", 1e-6),
+            ("ms", 1e-3),
+            ("s", 1.),
+            ("m", 60.),
+            ("h", 3600.),
+        ];
+
+        let v = s.trim();
+        let (num, mul) = UNITS
+            .iter()
+            .find_map(|(u, m)| v.strip_suffix(u).map(|n| (n, *m)))
+            .unwrap_or((v, 1.));
+
+        // Negative, infinite and too large values are rejected.
+        let dur = num
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .and_then(|n| Duration::try_from_secs_f64(n * mul).ok());
+        dur.map(Self).ok_or_else(|| {
+            ArgError::invalid_value(
+                "Expected duration (e.g. `30s`, `500ms` or `2m`).",
+                s,
+            )
+        })
+    }
+}
+
+impl FromArgStr for DurationArg {}
@@ -0,0 +1,67 @@
+use std::{fs, process::Command};
+
+use crate::err::{Error, Result};
+
+/// Modification of the environment of the measured program.
+#[derive(Debug, Clone)]
+pub enum EnvOp {
+    Set(String, String),
+    Unset(String),
+    Clear,
+    /// Load variables from the given file.
+    File(String),
+}
+
+impl EnvOp {
+    pub fn apply(&self, cmd: &mut Command) -> Result<()> {
+        match self {
+            Self::Set(k, v) => {
+                cmd.env(k, v);
+            }
+            Self::Unset(k) => {
+                cmd.env_remove(k);
+            }
+            Self::Clear => {
+                cmd.env_clear();
+            }
+            Self::File(path) => {
+                for (k, v) in read_env_file(path)? {
+                    cmd.env(k, v);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read file with lines in the format `KEY=VALUE`. Empty lines and lines
+/// starting with `#` are ignored.
+fn read_env_file(path: &str) -> Result<Vec<(String, String)>> {
+    let data = fs::read_to_string(path)
+        .map_err(|e| Error::FailedToOpen(path.to_string(), e))?;
+
+    let mut res = vec![];
+    for (i, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((k, v)) = line.split_once('=') else {
+            return Err(Error::InvalidEnvFile(path.to_string(), i + 1));
+        };
+
+        let v = v.trim();
+        let v = v
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .or_else(|| {
+                v.strip_prefix('\'').and_then(|v| v.strip_suffix('\''))
+            })
+            .unwrap_or(v);
+        res.push((k.trim().to_string(), v.to_string()));
+    }
+
+    Ok(res)
+}
@@ -0,0 +1,44 @@
+use std::str::FromStr;
+
+use pareg::{ArgError, FromArgStr};
+
+/// Exit code that is considered success.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExpectExit {
+    Code(i32),
+    Any,
+}
+
+impl ExpectExit {
+    pub fn matches(&self, code: Option<i32>) -> bool {
+        match self {
+            Self::Code(c) => code == Some(*c),
+            Self::Any => true,
+        }
+    }
+}
+
+impl Default for ExpectExit {
+    fn default() -> Self {
+        Self::Code(0)
+    }
+}
+
+impl FromStr for ExpectExit {
+    type Err = ArgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case("any") {
+            return Ok(Self::Any);
+        }
+
+        s.trim().parse().map(Self::Code).map_err(|_| {
+            ArgError::invalid_value(
+                "Expected exit code must be integer or `any`.",
+                s,
+            )
+        })
+    }
+}
+
+impl FromArgStr for ExpectExit {}
@@ -31,7 +31,46 @@ Mproc is small utility that allows measuring process run time and memory usage.
     Run the given program with the given arguments and measure its run time and
     peak memory.
 
+  The subcommands below must be the first argument and the flags are given
+  after their arguments. Use `{'y}--{'_}` to measure program with the name of
+  subcommand (`{'w}history{'_}`, `{'w}trend{'_}`, `{'w}run{'_}` or `{'w}suite{'_}`).
+
+  {'c}mproc history list {'gr}[{'dy}flags{'gr}]{'_}
+    List the benchmarks recorded with `{'y}--record{'_}`.
+
+  {'c}mproc history show {'w}<name> {'gr}[{'w}<session>{'gr}] [{'dy}flags{'gr}]{'_}
+    Show the results of recorded session of the benchmark. The last session
+    is shown by default. Sessions are numbered from 1 (the oldest).
+
+  {'c}mproc history diff {'w}<name> {'gr}[{'w}<base> <new>{'gr}] [{'dy}flags{'gr}]{'_}
+    Compare two recorded sessions of the benchmark. The last two sessions are
+    compared by default.
+
+  {'c}mproc {'gr}[{'c}history{'gr}] {'c}trend {'w}<name> {'gr}[{'dy}flags{'gr}]{'_}
+    Show how the time and memory of the benchmark evolved over the last 50
+    recorded sessions. Step changes of the results are detected and marked
+    with the session (and commit) where they happened.
+
+  {'c}mproc run {'w}<name>{'gr}... [{'dy}flags{'gr}]{'_}
+  {'c}mproc run {'dy}--all {'gr}[{'dy}flags{'gr}]{'_}
+    Run the given benchmarks (or all benchmarks) defined in the configuration
+    files. See {'g}Configuration{'_} below. After the results of each benchmark,
+    table with the results of all the benchmarks is printed. Fails if any of
+    the benchmarks failed, exceeded its thresholds or regressed. Benchmark
+    regressed if its time or memory is significantly (and at least by 5%)
+    higher than in the last session recorded under its name with
+    `{'y}--record{'_}`. With csv and json format only the table is printed and it
+    contains all the results.
+
+  {'c}mproc suite {'w}<file> {'gr}[{'dy}flags{'gr}]{'_}
+    Same as `{'c}run {'dy}--all{'_}`, but run the benchmarks from the given suite file.
+    The suite file has the same format as the configuration files. Its
+    defaults apply only to its benchmarks.
+
 {'g}Flags:
+  Boolean flags can be disabled with `{'y}--no-{'w}<flag>{'_}` (e.g. `{'y}--no-tui{'_}`). This
+  is useful to override flags from the configuration files.
+
   {'y}-h  -?  --help{'_}
     Print this help.
 
@@ -54,17 +93,269 @@ Mproc is small utility that allows measuring process run time and memory usage.
     Capture both stdout and stderr of the program. Same as \
     `{'y}-cout --cerr{'_}`.
 
+  {'y}--stdout-to {'w}<path>{'_}
+    Save stdout of the program to the given file. If the path contains
+    `{'w}{{run}}{'_}` it is replaced with the number of the run and each run has its
+    own file. Otherwise the output of all runs is appended to the file.
+
+  {'y}--stderr-to {'w}<path>{'_}
+    Same as `{'y}--stdout-to{'_}` but for stderr.
+
+  {'y}--only-failed-output{'_}
+    Keep only the output of runs that didn't exit with success or whose
+    output didn't match (see `{'y}--check-output{'_}`) when using `{'y}--stdout-to{'_}` or
+    `{'y}--stderr-to{'_}`.
+
+  {'y}--check-output{'_}
+    Check that stdout of the program is the same in all runs. The output is
+    not printed.
+
+  {'y}--check-stderr{'_}
+    Same as `{'y}--check-output{'_}`, but check also stderr.
+
+  {'y}--expect-output {'w}<path>{'_}
+    Check that stdout of the program is the same as contents of the given
+    file.
+
+  {'y}-i  --input {'w}<path>{'_}
+    Read stdin of the program from the given file. The file is opened again
+    for each run.
+
+  {'y}--no-stdin{'_}
+    Don't give the program any input (stdin is empty). `{'y}--stdin{'_}` reverts this.
+
   {'y}-r  --repeat {'w}<count>{'_}
     Measure the program the given number of times and produce summary.
 
+  {'y}--warmup {'w}<count>{'_}
+    Run the program the given number of times before the measurement. These
+    runs are not measured and their output is discarded.
+
+  {'y}--cwd {'w}<path>{'_}
+    Run the program in the given directory.
+
+  {'y}--tmpdir-per-run{'_}
+    Create new temporary directory for each run and run the program inside it.
+    Occurences of `{'w}{{tmpdir}}{'_}` in the program and its arguments are replaced
+    with the path to the directory and `{'w}TMPDIR{'_}` is set to it. The directory is
+    removed after the run.
+
+  {'y}--cpus {'w}<list>{'_}
+    Run the program only on the given CPUs. The list is comma separated list
+    of CPU indexes or ranges (e.g. `{'w}0,2-3{'_}`). Supported only on linux.
+
+  {'y}--nice {'w}<n>{'_}
+    Set the niceness of the program (from `{'i}-20{'_}` to `{'i}19{'_}`). Supported only
+    on linux.
+
+  {'y}--sched {'w}fifo|batch|idle{'_}
+    Set the scheduling policy of the program. Supported only on linux.
+
+  {'y}-e  --env {'w}<key>=<value>{'_}
+    Set environment variable for the program.
+
+  {'y}--unset-env {'w}<key>{'_}
+    Remove environment variable for the program.
+
+  {'y}--clear-env{'_}
+    Don't inherit any environment variables. Variables set by the previous
+    options are also removed.
+
+  {'y}--env-file {'w}<path>{'_}
+    Set environment variables from the given file. Each line of the file is in
+    the format `{'w}KEY=VALUE{'_}`. Empty lines and lines starting with `{'w}#{'_}` are
+    ignored.
+
+  {'y}--expect-exit {'w}<code>|any{'_}
+    Exit code of the program that is considered success. This is `{'i}0{'_}` by
+    default. With `{'i}any{'_}` all runs are considered successful.
+
+  {'y}--stop-on-failure{'_}
+    Stop measuring after the first failed run.
+
+  {'y}--ignore-failure{'_}
+    Don't consider any run failed. Same as `{'y}--expect-exit {'w}any{'_}`.
+
+  {'y}--min-runs {'w}<count>{'_}
+    Minimal number of runs when the number of runs is adaptive. This is `{'i}3{'_}`
+    by default.
+
+  {'y}--max-runs {'w}<count>{'_}
+    Maximal number of runs when the number of runs is adaptive. With
+    `{'y}--target-ci{'_}` and without `{'y}--max-time{'_}`, this is `{'i}1000{'_}` by default.
+    Without `{'y}--target-ci{'_}` and `{'y}--max-time{'_}`, the program runs exactly this
+    number of times. Adaptive measurement also stops after 10 failed runs
+    in row.
+
+  {'y}--target-ci {'w}<percent>{'_}
+    Measure until the confidence interval (see `{'y}--confidence{'_}`) of the mean
+    time is narrower than the given percentage of the mean (e.g. `{'w}2%{'_}` means
+    `{'w}mean ± 2%{'_}`).
+
+  {'y}--max-time {'w}<duration>{'_}
+    Measure until the given time elapses (e.g. `{'w}30s{'_}`, `{'w}500ms{'_}` or `{'w}2m{'_}`).
+    The started run is always finished. With multiple variants (e.g. with
+    `{'y}--parameter-scan{'_}`), this and the other limits of the number of runs
+    apply to each variant separately.
+
+  {'y}--confidence {'w}<percent>{'_}
+    Confidence level of the confidence intervals. The intervals are computed by
+    bootstrap from the measured samples. This is `{'i}95%{'_}` by default. The
+    significance level of comparisons is `{'i}100%{'_}` minus the confidence.
+
+  {'y}--save-samples {'w}<file>{'_}
+    Save the measured times and memory to the given file so that later
+    measurements can be compared with it using `{'y}--compare{'_}`. Has effect only
+    with multiple runs.
+
+  {'y}--compare {'w}<file>{'_}
+    Compare the measured times and memory with samples saved with
+    `{'y}--save-samples{'_}`. The difference is labeled as significant or within
+    noise by Mann-Whitney U test. When there are too few runs for it to be
+    significant, Welch's t-test is used instead. Has effect only with
+    multiple runs. With parameter sweep, each variant is compared with the
+    first variant.
+
+  {'y}--histogram{'_}
+    Show histogram of time and memory of the runs with markers for mean,
+    median and outliers. Only with human readable output format.
+
+  {'y}--no-progress{'_}
+    Don't show the live progress line on stderr during the measurements. The
+    progress is shown only if stderr is terminal. `{'y}--progress{'_}` reverts this.
+
+  {'y}--tui{'_}
+    Show full screen live view of the running program with its memory, CPU
+    usage of its threads, I/O rates and times of the previous runs. Press
+    `{'w}q{'_}` to stop after the current run or `{'w}k{'_}` to kill the program and stop. The
+    summary is printed after the view is closed. Stdin, stdout and stderr of
+    the program are not connected to the terminal. Process statistics are
+    available only on linux.
+
+  {'y}--record{'_}
+  {'y}--record{'w}=<name>{'_}
+    Append the results to the history store under the given name. If the
+    name is not given, the command is used as the name. With parameter
+    sweep, each variant is recorded as `{'w}<name> <param>=<value>{'_}`. The history
+    is stored in `{'w}$XDG_DATA_HOME/mproc/history{'_}` (by default
+    `{'w}~/.local/share/mproc/history{'_}`).
+
+  {'y}--strict{'_}
+    Refuse to measure multiple runs if the system is noisy. Without this,
+    only warnings are printed. The system is checked for high load average,
+    CPU frequency scaling governor other than `{'w}performance{'_}`, enabled turbo
+    boost, used swap and other processes with high CPU usage. The load is
+    also checked during the measurement and runs during load spike are
+    reported. Without this, processes with high CPU usage may be reported
+    only after the measurement, so that it doesn't have to wait for them to
+    be sampled. The checks are available only on linux.
+
+  {'y}--plot {'w}<file.svg>{'_}
+    Write chart of the results to the given SVG file. For single run it is
+    the memory of the program over time, for multiple runs violin and box
+    plots of the times and memory and for parameter sweep line charts of the
+    average time and memory for each value of the parameter.
+
+  {'y}--html {'w}<file>{'_}
+    Write self-contained HTML report of the measurement to the given file. It
+    contains the command, system information, summary of the results, table
+    of the individual runs, charts of the distributions and comparisons. The
+    measured data are embedded in the report as JSON.
+
+  {'y}-s  --shell{'_}
+  {'y}-s  --shell{'w}=<shell>{'_}
+    Run the program and its arguments as command in the given shell. The
+    default shell is `{'i}sh{'_}` (`{'i}cmd{'_}` on windows). This allows using pipes,
+    redirections and globbing in the measured command. The program is passed
+    to the shell as is, the arguments are quoted so that the shell doesn't
+    interpret them.
+
+  {'y}--calibrate-shell{'_}
+    When running in shell, measure the time it takes the shell to run empty
+    command and subtract it from the measured time.
+
   {'y}--color  --colour {'w}auto|always|never
   {'y}--color  --colour{'w}=auto|always|never{'_}
     Set the color mode. This will enable/disable color or automatically choose
     color when the output is terminal. This is `{'i}auto{'_}` by default.
 
+  {'y}-f  --format {'w}human|csv|json
+  {'y}-f  --format{'w}=human|csv|json{'_}
+    Set the format of the results. This is `{'i}human{'_}` by default. Csv output is
+    single table preceded by the information about the host as `{'w}#{'_}` comments.
+    Comparisons are additional columns of the table. Json output is single
+    object.
+
+  {'y}-v  --verbose{'_}
+    Show also information about the host of the measurement (kernel, CPU,
+    frequency governor, memory, load average, mproc version and git commit).
+    The information is always included in the csv and json formats.
+
+  {'y}--parameter-scan {'w}<name> <start> <end>{'_}
+    Measure the program for each integer value from `{'w}start{'_}` to `{'w}end{'_}`
+    (inclusive). Occurences of `{'w}{{name}}{'_}` in the program and its arguments are
+    replaced with the value. Results are shown as table.
+
+  {'y}--step {'w}<n>{'_}
+    Step used with `{'y}--parameter-scan{'_}`. This is `{'i}1{'_}` by default.
+
+  {'y}--parameter-list {'w}<name> <values>{'_}
+    Same as `{'y}--parameter-scan{'_}`, but the values are given as comma
+    separated list.
+
+  {'y}--order {'w}sequential|interleaved|random
+  {'y}--order{'w}=sequential|interleaved|random{'_}
+    Order of runs when measuring multiple variants of the program (e.g. with
+    `{'y}--parameter-scan{'_}`). `{'i}sequential{'_}` measures all runs of one variant
+    before the next variant, `{'i}interleaved{'_}` alternates the variants and
+    `{'i}random{'_}` shuffles all the runs. With adaptive number of runs (e.g.
+    `{'y}--target-ci{'_}`), `{'i}random{'_}` shuffles the variants in each round of runs. This
+    is `{'i}sequential{'_}` by default. Has no effect when there is only one variant.
+
+  {'y}--seed {'w}<seed>{'_}
+    Seed used with `{'y}--order {'w}random{'_}` and for bootstrap of confidence
+    intervals. By default, the order uses seed based on the current time and
+    bootstrap uses fixed seed. The seed of random order is shown with the
+    results.
+
+  {'y}--setup {'w}<command>{'_}
+    Run the given command in shell once before all the measurements.
+
+  {'y}--prepare {'w}<command>{'_}
+    Run the given command in shell before each measurement. The time and
+    memory of the command are not measured.
+
+  {'y}--cleanup {'w}<command>{'_}
+    Run the given command in shell once after all the measurements.
+
   {'y}--version{'_}
     Print version of mproc.
 
+{'g}Configuration:
+  Default flags and named benchmarks are read from `{'w}mproc.toml{'_}` in the
+  current directory and from `{'w}$XDG_CONFIG_HOME/mproc/config.toml{'_}` (by
+  default `{'w}~/.config/mproc/config.toml{'_}`). The project file takes precedence
+  over the user file and flags on the command line take precedence over
+  both. Keys are the long names of flags (`{'w}repeats{'_}` is also accepted for
+  `{'y}--repeat{'_}`) and unknown keys are errors. `{'i}true{'_}` enables flag, `{'i}false{'_}`
+  disables it, array gives multiple arguments to the flag (e.g. for
+  `{'y}--parameter-scan{'_}`) or repeats it (e.g. for `{'y}--unset-env{'_}`) and table gives
+  `{'w}key=value{'_}` for each entry (e.g. for `{'y}env{'_}`):
+
+    {'gr}[{'c}defaults{'gr}]
+    {'w}color {'gr}= {'i}\"always\"
+    {'w}warmup {'gr}= {'i}3
+
+    {'gr}[{'c}bench.sort{'gr}]
+    {'w}command {'gr}= {'i}\"./sort\"
+    {'w}args {'gr}= [{'i}\"data.txt\"{'gr}]
+    {'w}repeat {'gr}= {'i}20
+    {'w}env {'gr}= {{ {'w}LC_ALL {'gr}= {'i}\"C\" {'gr}}}
+    {'w}thresholds {'gr}= {{ {'w}time {'gr}= {'i}\"200ms\"{'gr}, {'w}memory {'gr}= {'i}\"64MiB\" {'gr}}}{'_}
+
+  `{'w}command{'_}` and `{'w}args{'_}` give the measured program. `{'w}thresholds{'_}` give the
+  maximum average time and peak memory of the benchmark.
+
  “ {'i}The one who has found his life will lose it, and the
    one who has lost his life on My account will find it. {'_}”
                                            {'w bold}✝ Matthew 10:39{'_}
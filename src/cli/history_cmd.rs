@@ -0,0 +1,61 @@
+use pareg::{Pareg, Result};
+
+/// Subcommand that works with the recorded history.
+#[derive(Debug, Clone)]
+pub enum HistoryCmd {
+    /// List the recorded benchmarks.
+    List,
+    /// Show session of the benchmark. The last session by default.
+    Show {
+        name: String,
+        session: Option<usize>,
+    },
+    /// Compare two sessions of the benchmark. The last two sessions by
+    /// default.
+    Diff {
+        name: String,
+        sessions: Option<(usize, usize)>,
+    },
+    /// Show how the results of the benchmark evolved.
+    Trend { name: String },
+}
+
+impl HistoryCmd {
+    /// Parse the subcommand and its arguments.
+    pub fn parse(args: &mut Pareg) -> Result<Self> {
+        match args.next() {
+            Some("list") => Ok(Self::List),
+            Some("show") => Ok(Self::Show {
+                name: args.next_arg()?,
+                session: next_index(args)?,
+            }),
+            Some("diff") => {
+                let name = args.next_arg()?;
+                let sessions = match next_index(args)? {
+                    Some(a) => Some((a, args.next_arg()?)),
+                    None => None,
+                };
+                Ok(Self::Diff { name, sessions })
+            }
+            Some("trend") => Ok(Self::Trend {
+                name: args.next_arg()?,
+            }),
+            Some(_) => args
+                .err_unknown_argument()
+                .hint("Expected `list`, `show`, `diff` or `trend`.")
+                .err(),
+            None => args
+                .err_no_more_arguments()
+                .hint("Expected `list`, `show`, `diff` or `trend`.")
+                .err(),
+        }
+    }
+}
+
+/// Parse the next argument as index of session if it is present.
+fn next_index(args: &mut Pareg) -> Result<Option<usize>> {
+    match args.peek() {
+        Some(a) if !a.starts_with('-') => args.next_arg().map(Some),
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,43 @@
+use std::str::FromStr;
+
+use pareg::{ArgError, FromArgStr};
+
+/// Amount of memory given as number with optional unit (`B`, `KiB`, `MiB`,
+/// `GiB`, `kB`, `MB`, `GB`). Number without unit is in bytes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MemoryArg(pub usize);
+
+impl FromStr for MemoryArg {
+    type Err = ArgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const UNITS: &[(&str, f64)] = &[
+            ("KiB", 1024.),
+            ("MiB", 1024. * 1024.),
+            ("GiB", 1024. * 1024. * 1024.),
+            ("kB", 1e3),
+            ("KB", 1e3),
+            ("MB", 1e6),
+            ("GB", 1e9),
+            ("B", 1.),
+        ];
+
+        let v = s.trim();
+        let (num, mul) = UNITS
+            .iter()
+            .find_map(|(u, m)| v.strip_suffix(u).map(|n| (n, *m)))
+            .unwrap_or((v, 1.));
+
+        match num.trim().parse::<f64>() {
+            Ok(n) if n >= 0. && n.is_finite() => {
+                Ok(Self((n * mul).round() as usize))
+            }
+            _ => Err(ArgError::invalid_value(
+                "Expected amount of memory (e.g. `512KiB` or `64MiB`).",
+                s,
+            )),
+        }
+    }
+}
+
+impl FromArgStr for MemoryArg {}
@@ -1,7 +1,24 @@
 mod args;
 mod color_mode;
+mod cpu_list;
+mod duration_arg;
+mod env_op;
+mod expect_exit;
 mod help;
+mod history_cmd;
+mod memory_arg;
 mod output;
+mod output_format;
 mod output_type;
+mod parameter;
+mod percent;
+mod run_cmd;
+mod run_order;
+mod sched_policy;
 
-pub use self::{args::*, color_mode::*, help::*, output::*, output_type::*};
+pub use self::{
+    args::*, color_mode::*, cpu_list::*, duration_arg::*, env_op::*,
+    expect_exit::*, help::*, history_cmd::*, memory_arg::*, output::*,
+    output_format::*, output_type::*, parameter::*, percent::*, run_cmd::*,
+    run_order::*, sched_policy::*,
+};
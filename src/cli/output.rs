@@ -1,46 +1,751 @@
-use termal::formatmc;
+use std::{fmt::Display, slice, time::Duration};
 
-use crate::{com_measure::ComMeasure, err::Result, measurement::Measurement};
+use termal::{codes, eprintacln, formatmc};
 
-use super::{ColorMode, OutputType};
+use crate::{
+    com_measure::ComMeasure,
+    comparison::Comparison,
+    err::Result,
+    get_dur_string, get_mem_string,
+    histogram::Histogram,
+    history::{ChangePoint, Session},
+    json::{JsonObject, ToJson},
+    measurement::Measurement,
+    metadata::Metadata,
+    suite::{Status, SuiteResult},
+};
+
+use super::{ColorMode, OutputFormat, OutputType, Parameter};
 
 #[derive(Debug)]
 pub struct Output {
     pub color: bool,
+    pub format: OutputFormat,
     pub out: OutputType,
+    /// Whether to print also the metadata in human readable format.
+    pub verbose: bool,
 }
 
 impl Output {
-    pub fn new(out: OutputType, color: ColorMode) -> Self {
+    pub fn new(
+        out: OutputType,
+        color: ColorMode,
+        format: OutputFormat,
+        verbose: bool,
+    ) -> Self {
         Self {
             color: out.color(color),
+            format,
             out,
+            verbose,
         }
     }
 
     pub fn print_measurement(
         &mut self,
         measurement: &Measurement,
+        md: &Metadata,
+    ) -> Result<()> {
+        let s = match self.format {
+            OutputFormat::Human => {
+                let color = self.color as usize;
+                format!("{measurement:-.color$}\n{}", self.metadata(md))
+            }
+            OutputFormat::Csv => format!(
+                "{}{}\n{}\n",
+                self.metadata(md),
+                Measurement::CSV_HEADER,
+                measurement.to_csv(),
+            ),
+            OutputFormat::Json => {
+                let obj =
+                    measurement.to_json().field("metadata", md.to_json());
+                format!("{}\n", obj.to_json())
+            }
+        };
+        self.out.print(s)
+    }
+
+    pub fn print_res_with(
+        &mut self,
+        n: impl Display,
+        r: Result<()>,
+    ) -> Result<()> {
+        let Err(e) = r else {
+            return Ok(());
+        };
+
+        if self.format != OutputFormat::Human {
+            eprintacln!("mproc: {'r}Failed to measure {n}: {'_}{e}");
+            return Ok(());
+        }
+
+        let s = formatmc!(
+            self.color,
+            "\nmproc: {'r}Failed to measure {n}: {'_}{e}\n"
+        );
+        self.out.print(s)
+    }
+
+    /// Print the results of repeated measurement with optional comparison
+    /// against other results.
+    pub fn print_com_measure(
+        &mut self,
+        cm: &ComMeasure,
+        comparison: Option<&Comparison>,
+        md: &Metadata,
+    ) -> Result<()> {
+        let s = match self.format {
+            OutputFormat::Human => {
+                let color = self.color as usize;
+                let mut s = format!("{cm:-.color$}");
+                if let Some(c) = comparison {
+                    s += "\n";
+                    s += &self.comparisons(slice::from_ref(c));
+                }
+                s + &self.metadata(md)
+            }
+            OutputFormat::Csv => match comparison {
+                Some(c) => format!(
+                    "{}{},{}\n{},{}\n",
+                    self.metadata(md),
+                    ComMeasure::CSV_HEADER,
+                    Comparison::CSV_COLUMNS,
+                    cm.to_csv(),
+                    c.to_csv_columns(),
+                ),
+                None => format!(
+                    "{}{}\n{}\n",
+                    self.metadata(md),
+                    ComMeasure::CSV_HEADER,
+                    cm.to_csv(),
+                ),
+            },
+            OutputFormat::Json => {
+                let mut obj = cm.to_json();
+                if let Some(c) = comparison {
+                    obj = obj.field("comparison", c.to_json());
+                }
+                let obj = obj.field("metadata", md.to_json());
+                format!("{}\n", obj.to_json())
+            }
+        };
+        self.out.print(s)
+    }
+
+    pub fn print_sweep(
+        &mut self,
+        param: &Parameter,
+        res: &[(String, ComMeasure)],
+        comparisons: &[Comparison],
+        md: &Metadata,
+    ) -> Result<()> {
+        let s = match self.format {
+            OutputFormat::Human => {
+                let mut s = self.sweep_table(param, res);
+                if let Some(seed) = md.seed
+                    && !self.verbose
+                {
+                    s += &formatmc!(self.color, "{'dm}Seed: {'_}{seed}\n");
+                }
+                if !comparisons.is_empty() {
+                    s += "\n";
+                    s += &self.comparisons(comparisons);
+                }
+                s + "\n" + &self.metadata(md)
+            }
+            OutputFormat::Csv => {
+                // Each variant is compared with the first variant.
+                let mut s = format!(
+                    "{}parameter,{},{}\n",
+                    self.metadata(md),
+                    ComMeasure::CSV_HEADER,
+                    Comparison::CSV_COLUMNS
+                );
+                let empty =
+                    ",".repeat(Comparison::CSV_COLUMNS.matches(',').count());
+                for (i, (v, cm)) in res.iter().enumerate() {
+                    let cmp = i
+                        .checked_sub(1)
+                        .and_then(|i| comparisons.get(i))
+                        .map_or_else(|| empty.clone(), |c| c.to_csv_columns());
+                    s += &format!("{},{},{cmp}\n", csv_field(v), cm.to_csv());
+                }
+                s
+            }
+            OutputFormat::Json => {
+                let results: Vec<_> = res
+                    .iter()
+                    .map(|(v, cm)| {
+                        JsonObject::new()
+                            .field("value", v)
+                            .field("stats", cm.to_json())
+                    })
+                    .collect();
+                let comparisons: Vec<_> =
+                    comparisons.iter().map(|c| c.to_json()).collect();
+                let obj = JsonObject::new()
+                    .field("parameter", param.name())
+                    .field("results", results)
+                    .field("comparisons", comparisons)
+                    .field("metadata", md.to_json());
+                format!("{}\n", obj.to_json())
+            }
+        };
+        self.out.print(s)
+    }
+
+    /// Print histograms of time and memory of the measured results. The
+    /// histograms are printed only with human readable output.
+    pub fn print_histograms(
+        &mut self,
+        res: &[(&str, &ComMeasure)],
+    ) -> Result<()> {
+        if self.format != OutputFormat::Human {
+            return Ok(());
+        }
+
+        let mut s = formatmc!(
+            self.color,
+            "{'gr}==============<< {'y}mproc histogram {'gr}>>=============={'_}
+"
+        );
+        let mut parts = vec![];
+        for (name, cm) in res {
+            let title = |q: &str| {
+                if name.is_empty() {
+                    q.to_string()
+                } else {
+                    format!("{name}: {}", q.to_lowercase())
+                }
+            };
+            let samples = cm.samples();
+            if let Some(h) = Histogram::new(&samples.times_ns()) {
+                parts.push(h.render(
+                    &title("Time"),
+                    self.color,
+                    codes::MAGENTA_FG,
+                    |v| get_dur_string(Duration::from_nanos(v as u64)),
+                ));
+            }
+            if let Some(h) = Histogram::new(&samples.memories_f64()) {
+                parts.push(h.render(
+                    &title("Memory"),
+                    self.color,
+                    codes::CYAN_FG,
+                    |v| get_mem_string(v as usize),
+                ));
+            }
+        }
+        s += &parts.join("\n");
+        self.out.print(s)
+    }
+
+    /// Print the comparisons of measured results.
+    pub fn print_comparisons(
+        &mut self,
+        comparisons: &[Comparison],
     ) -> Result<()> {
-        let color = self.color as usize;
-        let s = format!("{measurement:-.color$}\n");
+        let s = self.comparisons(comparisons);
         self.out.print(s)
     }
 
-    pub fn print_res_with(&mut self, n: usize, r: Result<()>) -> Result<()> {
-        let s = match r {
-            Ok(()) => String::new(),
-            Err(e) => formatmc!(
-                self.color,
-                "\nmproc: {'r}Failed to measure {n}: {'_}{e}\n"
+    fn comparisons(&self, comparisons: &[Comparison]) -> String {
+        match self.format {
+            OutputFormat::Human => {
+                let color = self.color as usize;
+                let mut s = String::new();
+                for (i, c) in comparisons.iter().enumerate() {
+                    if i == 0 {
+                        s += &format!("{c:-.color$}");
+                    } else {
+                        s += &format!("\n{c:.color$}");
+                    }
+                }
+                s
+            }
+            OutputFormat::Csv => {
+                let mut s = format!("{}\n", Comparison::CSV_HEADER);
+                for c in comparisons {
+                    s += &c.to_csv(&csv_field(&c.name));
+                }
+                s
+            }
+            OutputFormat::Json => {
+                let comparisons: Vec<_> =
+                    comparisons.iter().map(|c| c.to_json()).collect();
+                let obj = JsonObject::new().field("comparisons", comparisons);
+                format!("{}\n", obj.to_json())
+            }
+        }
+    }
+
+    /// Print the recorded benchmarks with number of their sessions and
+    /// their last session.
+    pub fn print_history_list(
+        &mut self,
+        names: &[(&str, usize, &Session)],
+    ) -> Result<()> {
+        let s = match self.format {
+            OutputFormat::Human => {
+                let mut rows = vec![
+                    ["Name", "Sessions", "Last recorded", "Command"]
+                        .map(str::to_string),
+                ];
+                rows.extend(names.iter().map(|(n, cnt, s)| {
+                    [
+                        n.to_string(),
+                        cnt.to_string(),
+                        s.date(),
+                        s.command.clone(),
+                    ]
+                }));
+                self.table(
+                    "mproc history",
+                    &rows,
+                    [
+                        codes::YELLOW_FG,
+                        codes::WHITE_FG,
+                        codes::MAGENTA_DARK_FG,
+                        codes::CYAN_DARK_FG,
+                    ],
+                )
+            }
+            OutputFormat::Csv => {
+                let mut s =
+                    "name,sessions,last_recorded,command\n".to_string();
+                for (n, cnt, last) in names {
+                    s += &format!(
+                        "{},{cnt},{},{}\n",
+                        csv_field(n),
+                        last.time,
+                        csv_field(&last.command)
+                    );
+                }
+                s
+            }
+            OutputFormat::Json => {
+                let names: Vec<_> = names
+                    .iter()
+                    .map(|(n, cnt, last)| {
+                        JsonObject::new()
+                            .field("name", *n)
+                            .field("sessions", *cnt)
+                            .field("last_recorded", last.time)
+                            .field("command", &last.command)
+                    })
+                    .collect();
+                format!("{}\n", names.to_json())
+            }
+        };
+        self.out.print(s)
+    }
+
+    /// Print the recorded session with its statistics.
+    pub fn print_session(&mut self, s: &Session) -> Result<()> {
+        let cm = s.stats();
+        let res = match self.format {
+            OutputFormat::Human => {
+                let color = self.color as usize;
+                formatmc!(
+                    self.color,
+                    "
+{'gr}===============<< {'y}mproc session {'gr}>>==============={'_}
+{'dy}Name    : {'y bold}{}{'_}
+{'dy}Recorded: {'_}{}
+{'dy}Commit  : {'_}{}
+{'dy}Command : {'_}{}
+
+",
+                    s.name,
+                    s.date(),
+                    s.commit().unwrap_or("-"),
+                    s.command,
+                ) + &format!("{cm:.color$}")
+                    + &self.metadata(&s.metadata)
+            }
+            OutputFormat::Csv => format!(
+                "{}name,recorded,command,{}\n{},{},{},{}\n",
+                self.metadata(&s.metadata),
+                ComMeasure::CSV_HEADER,
+                csv_field(&s.name),
+                s.time,
+                csv_field(&s.command),
+                cm.to_csv(),
             ),
+            OutputFormat::Json => {
+                let obj = cm
+                    .to_json()
+                    .field("name", &s.name)
+                    .field("recorded", s.time)
+                    .field("command", &s.command)
+                    .field("metadata", s.metadata.to_json());
+                format!("{}\n", obj.to_json())
+            }
+        };
+        self.out.print(res)
+    }
+
+    /// Print how the results of the sessions evolved with the detected step
+    /// changes. The sessions are given with their 1 based index.
+    pub fn print_trend(
+        &mut self,
+        name: &str,
+        sessions: &[(usize, &Session)],
+        changes: &[ChangePoint],
+    ) -> Result<()> {
+        let stats: Vec<_> = sessions.iter().map(|(_, s)| s.stats()).collect();
+        let change = |i: usize, quantity: &str| {
+            changes
+                .iter()
+                .find(|c| c.session == i && c.quantity == quantity)
+                .map(|c| c.change)
+        };
+        let s = match self.format {
+            OutputFormat::Human => {
+                let mut rows = vec![
+                    [
+                        "#",
+                        "Recorded",
+                        "Commit",
+                        "Runs",
+                        "Avg time",
+                        "Peak memory",
+                        "Change",
+                    ]
+                    .map(str::to_string),
+                ];
+                for ((i, s), cm) in sessions.iter().zip(&stats) {
+                    let change = ["time", "memory"]
+                        .into_iter()
+                        .filter_map(|q| {
+                            change(*i, q)
+                                .map(|c| format!("{q} {:+.1}%", c * 100.))
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    rows.push([
+                        i.to_string(),
+                        s.date(),
+                        s.commit().unwrap_or("-").to_string(),
+                        cm.attempts().to_string(),
+                        avg_time_with_ci(cm),
+                        cm.worst_memory()
+                            .map_or("-".to_string(), get_mem_string),
+                        change,
+                    ]);
+                }
+                let mut res = self.table(
+                    "mproc trend",
+                    &rows,
+                    [
+                        codes::YELLOW_FG,
+                        codes::MAGENTA_DARK_FG,
+                        codes::CYAN_DARK_FG,
+                        codes::WHITE_FG,
+                        codes::MAGENTA_FG,
+                        codes::CYAN_FG,
+                        codes::RED_FG,
+                    ],
+                );
+                for c in changes {
+                    let s = sessions.iter().find(|s| s.0 == c.session);
+                    let commit = s.and_then(|s| s.1.commit()).unwrap_or("-");
+                    let dir = if c.change > 0. { "more" } else { "less" };
+                    res += &formatmc!(
+                        self.color,
+                        "{'r bold}Step change{'_ dm} at session {'_}{}\
+                        {'dm} (commit {'_}{commit}{'dm}): {'_ r}{} \
+                        {:.1}% {dir}{'_}\n",
+                        c.session,
+                        c.quantity,
+                        c.change.abs() * 100.,
+                    );
+                }
+                res
+            }
+            OutputFormat::Csv => {
+                let mut res = format!(
+                    "session,recorded,commit,time_change,memory_change,{}\n",
+                    ComMeasure::CSV_HEADER
+                );
+                let opt = |v: Option<f64>| {
+                    v.map(|v| v.to_string()).unwrap_or_default()
+                };
+                for ((i, s), cm) in sessions.iter().zip(&stats) {
+                    res += &format!(
+                        "{i},{},{},{},{},{}\n",
+                        s.time,
+                        s.commit().unwrap_or_default(),
+                        opt(change(*i, "time")),
+                        opt(change(*i, "memory")),
+                        cm.to_csv()
+                    );
+                }
+                res
+            }
+            OutputFormat::Json => {
+                let sessions: Vec<_> = sessions
+                    .iter()
+                    .zip(&stats)
+                    .map(|((i, s), cm)| {
+                        JsonObject::new()
+                            .field("session", *i)
+                            .field("recorded", s.time)
+                            .field("commit", &s.metadata.git_commit)
+                            .field("stats", cm.to_json())
+                    })
+                    .collect();
+                let changes: Vec<_> = changes
+                    .iter()
+                    .map(|c| {
+                        JsonObject::new()
+                            .field("session", c.session)
+                            .field("quantity", c.quantity)
+                            .field("change", c.change)
+                    })
+                    .collect();
+                let obj = JsonObject::new()
+                    .field("name", name)
+                    .field("sessions", sessions)
+                    .field("change_points", changes);
+                format!("{}\n", obj.to_json())
+            }
         };
         self.out.print(s)
     }
 
-    pub fn print_com_measure(&mut self, cm: &ComMeasure) -> Result<()> {
-        let color = self.color as usize;
-        let s = format!("{cm:-.color$}");
+    /// Print the combined results of the benchmarks of suite.
+    pub fn print_suite(&mut self, results: &[SuiteResult]) -> Result<()> {
+        let s = match self.format {
+            OutputFormat::Human => {
+                let mut rows = vec![
+                    [
+                        "Benchmark",
+                        "Status",
+                        "Runs",
+                        "Avg time",
+                        "Peak memory",
+                        "Change",
+                    ]
+                    .map(str::to_string),
+                ];
+                let opt = |v: Option<String>| v.unwrap_or("-".to_string());
+                for r in results {
+                    let cm = r.stats.as_ref();
+                    let change = r
+                        .changes()
+                        .into_iter()
+                        .filter_map(|(q, c)| {
+                            c.map(|c| format!("{q} {:+.1}%", c * 100.))
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    rows.push([
+                        r.name.clone(),
+                        r.status.name().to_string(),
+                        opt(cm.map(|cm| {
+                            format!("{}/{}", cm.success(), cm.attempts())
+                        })),
+                        opt(cm.map(avg_time_with_ci)),
+                        opt(cm
+                            .and_then(|cm| cm.worst_memory())
+                            .map(get_mem_string)),
+                        change,
+                    ]);
+                }
+                let mut res = self.table(
+                    "mproc suite",
+                    &rows,
+                    [
+                        codes::YELLOW_FG,
+                        codes::WHITE_FG,
+                        codes::GREEN_DARK_FG,
+                        codes::MAGENTA_FG,
+                        codes::CYAN_FG,
+                        codes::MAGENTA_DARK_FG,
+                    ],
+                );
+                for r in results {
+                    for p in &r.problems {
+                        res += &formatmc!(
+                            self.color,
+                            "{'r bold}{}{'_} {}{'dm}: {'_}{p}\n",
+                            r.status.name(),
+                            r.name,
+                        );
+                    }
+                }
+                let passed =
+                    results.iter().filter(|r| r.status == Status::Ok).count();
+                res += &formatmc!(
+                    self.color,
+                    "{'dm}Passed: {'_}{passed}/{}\n",
+                    results.len()
+                );
+                res
+            }
+            OutputFormat::Csv => {
+                let mut res = format!(
+                    "benchmark,status,time_change,memory_change,problems,{}\n",
+                    ComMeasure::CSV_HEADER
+                );
+                let empty =
+                    ",".repeat(ComMeasure::CSV_HEADER.matches(',').count());
+                for r in results {
+                    let [(_, time), (_, memory)] = r.changes();
+                    let opt = |v: Option<f64>| {
+                        v.map(|v| v.to_string()).unwrap_or_default()
+                    };
+                    res += &format!(
+                        "{},{},{},{},{},{}\n",
+                        csv_field(&r.name),
+                        r.status.name(),
+                        opt(time),
+                        opt(memory),
+                        csv_field(&r.problems.join(" ")),
+                        r.stats
+                            .as_ref()
+                            .map_or(empty.clone(), |cm| cm.to_csv()),
+                    );
+                }
+                res
+            }
+            OutputFormat::Json => {
+                let benchmarks: Vec<_> =
+                    results.iter().map(|r| r.to_json()).collect();
+                let passed =
+                    results.iter().filter(|r| r.status == Status::Ok).count();
+                let obj = JsonObject::new()
+                    .field("benchmarks", benchmarks)
+                    .field("passed", passed)
+                    .field("failed", results.len() - passed);
+                format!("{}\n", obj.to_json())
+            }
+        };
         self.out.print(s)
     }
+
+    /// Get the metadata as separate section. In human readable format, it
+    /// is shown only in verbose mode. In csv, it is `#` comments that precede
+    /// the table.
+    fn metadata(&self, md: &Metadata) -> String {
+        match self.format {
+            OutputFormat::Human if self.verbose => {
+                let color = self.color as usize;
+                format!("{md:-.color$}")
+            }
+            OutputFormat::Human | OutputFormat::Json => String::new(),
+            OutputFormat::Csv => {
+                let mut s = String::new();
+                for (k, v) in md.fields(false) {
+                    s += &format!("# {k}: {}\n", v.replace('\n', " "));
+                }
+                s
+            }
+        }
+    }
+
+    fn sweep_table(
+        &self,
+        param: &Parameter,
+        res: &[(String, ComMeasure)],
+    ) -> String {
+        let mut rows = vec![[
+            param.name().to_string(),
+            "Avg time".to_string(),
+            "Best time".to_string(),
+            "Worst time".to_string(),
+            "Avg memory".to_string(),
+            "Worst memory".to_string(),
+            "Success".to_string(),
+        ]];
+
+        let opt = |v: Option<String>| v.unwrap_or_else(|| "-".to_string());
+        for (v, cm) in res {
+            rows.push([
+                v.clone(),
+                avg_time_with_ci(cm),
+                opt(cm.best_time().map(get_dur_string)),
+                opt(cm.worst_time().map(get_dur_string)),
+                opt(cm.avg_memory().map(get_mem_string)),
+                opt(cm.worst_memory().map(get_mem_string)),
+                format!("{}/{}", cm.success(), cm.success() + cm.failure()),
+            ]);
+        }
+
+        const COLORS: [&str; 7] = [
+            codes::YELLOW_FG,
+            codes::MAGENTA_FG,
+            codes::MAGENTA_DARK_FG,
+            codes::MAGENTA_DARK_FG,
+            codes::CYAN_FG,
+            codes::CYAN_DARK_FG,
+            codes::GREEN_DARK_FG,
+        ];
+        self.table("mproc results", &rows, COLORS)
+    }
+
+    /// Render table with the given title. The first row is header and
+    /// `colors` are the colors of the columns.
+    fn table<const N: usize>(
+        &self,
+        title: &str,
+        rows: &[[String; N]],
+        colors: [&str; N],
+    ) -> String {
+        let mut widths = [0; N];
+        for r in rows {
+            for (w, c) in widths.iter_mut().zip(r) {
+                *w = (*w).max(c.chars().count());
+            }
+        }
+
+        let mut s = formatmc!(
+            self.color,
+            "
+{'gr}===============<< {'y}{title} {'gr}>>==============={'_}
+"
+        );
+        for (i, r) in rows.iter().enumerate() {
+            for (j, (c, w)) in r.iter().zip(widths).enumerate() {
+                if j != 0 {
+                    s += "  ";
+                }
+                let pad = if j + 1 == r.len() {
+                    0
+                } else {
+                    w - c.chars().count()
+                };
+                if self.color {
+                    let col = if i == 0 { codes::GREEN_FG } else { colors[j] };
+                    s += &format!("{col}{c}{}{:pad$}", codes::RESET, "");
+                } else {
+                    s += &format!("{c}{:pad$}", "");
+                }
+            }
+            s += "\n";
+        }
+        s
+    }
+}
+
+/// Get the average time with its confidence interval.
+fn avg_time_with_ci(cm: &ComMeasure) -> String {
+    let avg = get_dur_string(cm.avg_time());
+    match cm.estimates().time_mean {
+        Some(e) => format!(
+            "{avg} ± {}",
+            get_dur_string(Duration::from_nanos(e.half_width() as u64))
+        ),
+        None => avg,
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
 }
@@ -0,0 +1,9 @@
+use pareg::FromArg;
+
+#[derive(Copy, Clone, FromArg, Default, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    Csv,
+    Json,
+}
@@ -14,6 +14,8 @@ pub enum OutputType {
     Stdout,
     FilePath(String),
     File(BufWriter<File>),
+    /// The output is discarded.
+    Null,
 }
 
 impl OutputType {
@@ -24,6 +26,21 @@ impl OutputType {
         Ok(())
     }
 
+    /// Create output to the same destination. Output to file continues
+    /// after the data written so far.
+    pub fn try_clone(&mut self) -> Result<Self> {
+        Ok(match self {
+            Self::Stderr => Self::Stderr,
+            Self::Stdout => Self::Stdout,
+            Self::Null => Self::Null,
+            Self::FilePath(f) => Self::FilePath(f.clone()),
+            Self::File(f) => {
+                f.flush()?;
+                Self::File(BufWriter::new(f.get_ref().try_clone()?))
+            }
+        })
+    }
+
     pub fn print(&mut self, s: impl AsRef<str>) -> Result<()> {
         match self {
             Self::Stderr => {
@@ -40,6 +57,7 @@ impl OutputType {
             Self::File(f) => {
                 write!(f, "{}", s.as_ref())?;
             }
+            Self::Null => {}
         }
 
         Ok(())
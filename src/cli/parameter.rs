@@ -0,0 +1,48 @@
+/// Parameter that is substituted into the program arguments.
+#[derive(Debug)]
+pub enum Parameter {
+    /// Range of integer values.
+    Scan {
+        name: String,
+        start: i64,
+        end: i64,
+        step: i64,
+    },
+    /// List of values.
+    List { name: String, values: Vec<String> },
+}
+
+impl Parameter {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Scan { name, .. } | Self::List { name, .. } => name,
+        }
+    }
+
+    /// Get all the values of the parameter in order.
+    pub fn values(&self) -> Vec<String> {
+        match self {
+            Self::Scan {
+                start, end, step, ..
+            } => {
+                let step = if start <= end { *step } else { -*step };
+                let mut res = vec![];
+                let mut v = *start;
+                while (step > 0 && v <= *end) || (step < 0 && v >= *end) {
+                    res.push(v.to_string());
+                    let Some(n) = v.checked_add(step) else {
+                        break;
+                    };
+                    v = n;
+                }
+                res
+            }
+            Self::List { values, .. } => values.clone(),
+        }
+    }
+
+    /// Replace all occurences of `{name}` in `s` with the given value.
+    pub fn substitute(&self, s: &str, value: &str) -> String {
+        s.replace(&format!("{{{}}}", self.name()), value)
+    }
+}
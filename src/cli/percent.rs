@@ -0,0 +1,26 @@
+use std::str::FromStr;
+
+use pareg::{ArgError, FromArgStr};
+
+/// Percentage given as number with optional `%` suffix. The value is stored
+/// as fraction (`2%` is `0.02`).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Percent(pub f64);
+
+impl FromStr for Percent {
+    type Err = ArgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let v = s.trim();
+        let v = v.strip_suffix('%').unwrap_or(v);
+        match v.trim().parse::<f64>() {
+            Ok(v) if v > 0. && v.is_finite() => Ok(Self(v / 100.)),
+            _ => Err(ArgError::invalid_value(
+                "Expected positive percentage (e.g. `2%`).",
+                s,
+            )),
+        }
+    }
+}
+
+impl FromArgStr for Percent {}
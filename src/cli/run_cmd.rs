@@ -0,0 +1,37 @@
+use pareg::{Pareg, Result};
+
+/// Benchmarks that should be run.
+#[derive(Debug, Clone)]
+pub enum RunCmd {
+    /// Run all the defined benchmarks.
+    All,
+    /// Run the benchmarks with the given names.
+    Names(Vec<String>),
+    /// Run all the benchmarks from the given suite file.
+    Suite(String),
+}
+
+impl RunCmd {
+    /// Parse the arguments of the subcommand. Names of the benchmarks end at
+    /// the first flag.
+    pub fn parse(args: &mut Pareg) -> Result<Self> {
+        match args.next() {
+            Some("--all") => Ok(Self::All),
+            Some(a) if !a.starts_with('-') => {
+                let mut names = vec![args.cur_arg()?];
+                while args.peek().is_some_and(|a| !a.starts_with('-')) {
+                    names.push(args.next_arg()?);
+                }
+                Ok(Self::Names(names))
+            }
+            Some(_) => args
+                .err_unknown_argument()
+                .hint("Expected name of benchmark or `--all`.")
+                .err(),
+            None => args
+                .err_no_more_arguments()
+                .hint("Expected name of benchmark or `--all`.")
+                .err(),
+        }
+    }
+}
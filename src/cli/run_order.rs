@@ -0,0 +1,56 @@
+use pareg::FromArg;
+
+use crate::rng::Rng;
+
+/// Order in which runs of multiple variants are measured.
+#[derive(Copy, Clone, FromArg, Default, Debug, PartialEq, Eq)]
+pub enum RunOrder {
+    /// All runs of one variant and then all runs of the next variant.
+    #[default]
+    Sequential,
+    /// One run of each variant and then again.
+    Interleaved,
+    /// Random order.
+    Random,
+}
+
+impl RunOrder {
+    /// Get the order of runs as pairs of variant index and run index.
+    pub fn schedule(
+        &self,
+        variants: usize,
+        runs: usize,
+        seed: u64,
+    ) -> Vec<(usize, usize)> {
+        match self {
+            Self::Sequential => (0..variants)
+                .flat_map(|v| (0..runs).map(move |r| (v, r)))
+                .collect(),
+            Self::Interleaved => (0..runs)
+                .flat_map(|r| (0..variants).map(move |v| (v, r)))
+                .collect(),
+            Self::Random => {
+                let mut res = Self::Sequential.schedule(variants, runs, seed);
+                Rng::new(seed).shuffle(&mut res);
+                // Keep the run numbers of each variant in order.
+                let mut cnt = vec![0; variants];
+                for (v, r) in &mut res {
+                    *r = cnt[*v];
+                    cnt[*v] += 1;
+                }
+                res
+            }
+        }
+    }
+
+    /// Get the next round of runs with adaptive number of runs as indices of
+    /// the variants. `pending` are the variants that need more runs.
+    pub fn round(&self, mut pending: Vec<usize>, rng: &mut Rng) -> Vec<usize> {
+        match self {
+            Self::Sequential => pending.truncate(1),
+            Self::Interleaved => {}
+            Self::Random => rng.shuffle(&mut pending),
+        }
+        pending
+    }
+}
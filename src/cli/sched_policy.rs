@@ -0,0 +1,9 @@
+use pareg::FromArg;
+
+/// Scheduling policy of the measured program.
+#[derive(Copy, Clone, FromArg, Debug, PartialEq, Eq)]
+pub enum SchedPolicy {
+    Fifo,
+    Batch,
+    Idle,
+}
@@ -1,4 +1,5 @@
 use std::{
+    cell::OnceCell,
     fmt::Display,
     io::{self, IsTerminal},
     process::Command,
@@ -7,10 +8,77 @@ use std::{
 
 use termal::{formatmc, writemcln};
 
-use crate::{err::Result, get_mem_string, measurement::Measurement};
+use crate::{
+    DEFAULT_CONFIDENCE,
+    cli::ExpectExit,
+    err::Result,
+    get_mem_string,
+    json::JsonObject,
+    measurement::Measurement,
+    output_check::OutputCheck,
+    rng::Rng,
+    samples::Samples,
+    stats::{self, Estimate},
+};
+
+/// Settings of combined measurement.
+#[derive(Debug, Copy, Clone)]
+pub struct MeasureOpts {
+    /// Time that is subtracted from each measured time.
+    pub overhead: Duration,
+    pub expect_exit: ExpectExit,
+    /// Confidence level of the confidence intervals (`0` to `1`).
+    pub confidence: f64,
+    /// Seed used for bootstrap.
+    pub seed: u64,
+}
+
+/// Estimates of the statistics with confidence intervals. Times are in
+/// nanoseconds and memory is in bytes.
+#[derive(Debug, Copy, Clone)]
+pub struct Estimates {
+    pub confidence: f64,
+    pub time_mean: Option<Estimate>,
+    pub time_median: Option<Estimate>,
+    pub memory_mean: Option<Estimate>,
+    pub memory_median: Option<Estimate>,
+}
+
+/// Result of single measured run.
+#[derive(Debug, Copy, Clone)]
+pub struct RunRecord {
+    pub time: Duration,
+    pub memory: Option<usize>,
+    pub exit_code: Option<i32>,
+    /// Whether the exit code is the expected exit code.
+    pub success: bool,
+}
+
+impl From<&Measurement> for RunRecord {
+    fn from(m: &Measurement) -> Self {
+        Self {
+            time: m.time,
+            memory: m.memory.as_ref().ok().copied(),
+            exit_code: m.exit_code,
+            success: m.success,
+        }
+    }
+}
+
+impl Default for MeasureOpts {
+    fn default() -> Self {
+        Self {
+            overhead: Duration::ZERO,
+            expect_exit: ExpectExit::default(),
+            confidence: DEFAULT_CONFIDENCE,
+            seed: 0,
+        }
+    }
+}
 
 pub struct ComMeasure {
-    cmd: Command,
+    opts: MeasureOpts,
+    output_check: Option<OutputCheck>,
     atempts: usize,
     measured: usize,
     total_time: Duration,
@@ -22,12 +90,18 @@ pub struct ComMeasure {
     total_memory: usize,
     best_memory: usize,
     worst_memory: usize,
+    samples: Samples,
+    runs: Vec<RunRecord>,
+    /// Estimates computed from the current samples. Bootstrap is slow, so
+    /// they are computed only once after each change of the samples.
+    estimates: OnceCell<Estimates>,
 }
 
 impl ComMeasure {
-    pub fn new(cmd: Command) -> Self {
+    pub fn new(opts: MeasureOpts, output_check: Option<OutputCheck>) -> Self {
         Self {
-            cmd,
+            opts,
+            output_check,
             atempts: 0,
             measured: 0,
             total_time: Duration::ZERO,
@@ -39,36 +113,238 @@ impl ComMeasure {
             total_memory: 0,
             best_memory: usize::MAX,
             worst_memory: 0,
+            samples: Samples::default(),
+            runs: vec![],
+            estimates: OnceCell::new(),
         }
     }
 
-    pub fn measure(&mut self) -> Result<()> {
+    /// Measure the command and add it to the statistics. Failure to get
+    /// memory is not returned as error, but it is part of the result.
+    pub fn measure(&mut self, cmd: &mut Command) -> Result<Measurement> {
+        self.add(Measurement::measure(cmd))
+    }
+
+    /// Add result of single attempt to measure the command to the
+    /// statistics.
+    pub fn add(&mut self, m: Result<Measurement>) -> Result<Measurement> {
         self.atempts += 1;
 
-        let m = Measurement::measure(&mut self.cmd)?;
+        let mut m = m?;
+        m.time = m.time.saturating_sub(self.opts.overhead);
+        m.success = self.opts.expect_exit.matches(m.exit_code);
+        self.record(RunRecord::from(&m));
+
+        Ok(m)
+    }
+
+    /// Add already measured run to the statistics. The time and success are
+    /// used as is.
+    pub fn add_record(&mut self, r: RunRecord) {
+        self.atempts += 1;
+        self.record(r);
+    }
 
+    fn record(&mut self, r: RunRecord) {
+        self.estimates.take();
         self.measured += 1;
-        self.total_time += m.time;
-        self.best_time = self.best_time.min(m.time);
-        self.worst_time = self.worst_time.max(m.time);
-
-        match m.exit_code {
-            Some(0) => self.success += 1,
-            Some(_) => self.failure += 1,
-            _ => {}
+        self.total_time += r.time;
+        self.best_time = self.best_time.min(r.time);
+        self.worst_time = self.worst_time.max(r.time);
+        self.samples.times.push(r.time);
+
+        if r.success {
+            self.success += 1;
+        } else if r.exit_code.is_some() {
+            self.failure += 1;
         }
 
-        let m = m.memory?;
+        if let Some(mem) = r.memory {
+            self.memory_cnt += 1;
+            self.total_memory += mem;
+            self.best_memory = self.best_memory.min(mem);
+            self.worst_memory = self.worst_memory.max(mem);
+            self.samples.memories.push(mem);
+        }
 
-        self.memory_cnt += 1;
-        self.total_memory += m;
-        self.best_memory = self.best_memory.min(m);
-        self.worst_memory = self.worst_memory.max(m);
+        self.runs.push(r);
+    }
 
-        Ok(())
+    pub fn avg_time(&self) -> Duration {
+        self.total_time / self.measured.max(1) as u32
+    }
+
+    /// Half width of the confidence interval of mean time relative to the
+    /// mean.
+    pub fn time_rel_ci(&self) -> f64 {
+        let times: Vec<_> = self
+            .samples
+            .times
+            .iter()
+            .map(Duration::as_secs_f64)
+            .collect();
+        stats::rel_ci(&times, self.opts.confidence)
+    }
+
+    /// Estimate mean and median of time and memory with bootstrap
+    /// confidence intervals.
+    pub fn estimates(&self) -> Estimates {
+        *self.estimates.get_or_init(|| self.bootstrap())
+    }
+
+    fn bootstrap(&self) -> Estimates {
+        let mut rng = Rng::new(self.opts.seed);
+        let conf = self.opts.confidence;
+        let times = self.samples.times_ns();
+        let mems = self.samples.memories_f64();
+
+        Estimates {
+            confidence: conf,
+            time_mean: stats::bootstrap(
+                &times,
+                |v| stats::mean(v),
+                conf,
+                &mut rng,
+            ),
+            time_median: stats::bootstrap(
+                &times,
+                stats::median,
+                conf,
+                &mut rng,
+            ),
+            memory_mean: stats::bootstrap(
+                &mems,
+                |v| stats::mean(v),
+                conf,
+                &mut rng,
+            ),
+            memory_median: stats::bootstrap(
+                &mems,
+                stats::median,
+                conf,
+                &mut rng,
+            ),
+        }
+    }
+
+    pub fn samples(&self) -> &Samples {
+        &self.samples
+    }
+
+    /// Get the results of the individual measured runs.
+    pub fn runs(&self) -> &[RunRecord] {
+        &self.runs
+    }
+
+    pub fn attempts(&self) -> usize {
+        self.atempts
+    }
+
+    pub fn best_time(&self) -> Option<Duration> {
+        (self.measured != 0).then_some(self.best_time)
+    }
+
+    pub fn worst_time(&self) -> Option<Duration> {
+        (self.measured != 0).then_some(self.worst_time)
+    }
+
+    pub fn avg_memory(&self) -> Option<usize> {
+        (self.memory_cnt != 0).then(|| self.total_memory / self.memory_cnt)
+    }
+
+    pub fn best_memory(&self) -> Option<usize> {
+        (self.memory_cnt != 0).then_some(self.best_memory)
+    }
+
+    pub fn worst_memory(&self) -> Option<usize> {
+        (self.memory_cnt != 0).then_some(self.worst_memory)
+    }
+
+    pub fn output_check_mut(&mut self) -> Option<&mut OutputCheck> {
+        self.output_check.as_mut()
+    }
+
+    pub fn output_mismatches(&self) -> Option<usize> {
+        self.output_check.as_ref().map(|c| c.mismatches())
+    }
+
+    pub fn success(&self) -> usize {
+        self.success
+    }
+
+    pub fn failure(&self) -> usize {
+        self.failure
+    }
+
+    pub fn to_json(&self) -> JsonObject {
+        let est = self.estimates();
+        JsonObject::new()
+            .field("attempts", self.atempts)
+            .field("runs", self.measured)
+            .field("success", self.success)
+            .field("failure", self.failure)
+            .field("avg_time_ns", self.avg_time())
+            .field("best_time_ns", self.best_time())
+            .field("worst_time_ns", self.worst_time())
+            .field("avg_memory", self.avg_memory())
+            .field("best_memory", self.best_memory())
+            .field("worst_memory", self.worst_memory())
+            .field("output_mismatches", self.output_mismatches())
+            .field("confidence", est.confidence)
+            .field("avg_time_ci_ns", est.time_mean.map(ci_json))
+            .field("median_time_ns", est.time_median.map(|e| e.value as u128))
+            .field("median_time_ci_ns", est.time_median.map(ci_json))
+            .field("avg_memory_ci", est.memory_mean.map(ci_json))
+            .field("median_memory", est.memory_median.map(|e| e.value as u128))
+            .field("median_memory_ci", est.memory_median.map(ci_json))
+    }
+
+    pub const CSV_HEADER: &str = "attempts,runs,success,failure,avg_time_ns,\
+        best_time_ns,worst_time_ns,avg_memory,best_memory,worst_memory,\
+        output_mismatches,confidence,avg_time_ci_low_ns,avg_time_ci_high_ns,\
+        median_time_ns,median_time_ci_low_ns,median_time_ci_high_ns,\
+        avg_memory_ci_low,avg_memory_ci_high,median_memory,\
+        median_memory_ci_low,median_memory_ci_high";
+
+    pub fn to_csv(&self) -> String {
+        fn opt(v: Option<impl ToString>) -> String {
+            v.map(|v| v.to_string()).unwrap_or_default()
+        }
+
+        let est = self.estimates();
+        let ci = |e: Option<Estimate>| match e {
+            Some(e) => format!("{},{}", e.low as u128, e.high as u128),
+            None => ",".to_string(),
+        };
+
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.atempts,
+            self.measured,
+            self.success,
+            self.failure,
+            self.avg_time().as_nanos(),
+            opt(self.best_time().map(|t| t.as_nanos())),
+            opt(self.worst_time().map(|t| t.as_nanos())),
+            opt(self.avg_memory()),
+            opt(self.best_memory()),
+            opt(self.worst_memory()),
+            opt(self.output_mismatches()),
+            est.confidence,
+            ci(est.time_mean),
+            opt(est.time_median.map(|e| e.value as u128)),
+            ci(est.time_median),
+            ci(est.memory_mean),
+            opt(est.memory_median.map(|e| e.value as u128)),
+            ci(est.memory_median),
+        )
     }
 }
 
+fn ci_json(e: Estimate) -> Vec<u128> {
+    vec![e.low as u128, e.high as u128]
+}
+
 impl Display for ComMeasure {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let color = f
@@ -91,7 +367,6 @@ impl Display for ComMeasure {
             (formatmc!(color, "{'dr}"), formatmc!(color, "{'r bold}"))
         };
 
-        let dmeasured = self.measured.max(1) as u32;
         let dsc = (self.success + self.failure).max(1) as f32;
         let dmem = self.memory_cnt.max(1);
 
@@ -99,27 +374,80 @@ impl Display for ComMeasure {
             f,
             color,
             "Runs   : {'w bold}{measured}{'_}/{atempts}
-{ds}Success: {bs}{'bold}{sc_rate} {'_}{ds}({success}/{sc_total})
-
-{'dm}Avg time  : {'m bold}{avg_time:?} {'_ dm}({total_time:?}/{measured}){'_}
-{'dm}Best time : {'m bold}{best_time:?}{'_}
-{'dm}Worst time: {'m bold}{worst_time:?}{'_}
-
-{'dc}Avg memory  : {'c bold}{avg_memory} {'_ dc}({total_memory}/{measured}){'_}
-{'dc}Best memory : {'c bold}{best_memory}{'_}
-{'dc}Worst memory: {'c bold}{worst_memory}{'_}
-",
+{ds}Success: {bs}{'bold}{sc_rate} {'_}{ds}({success}/{sc_total}){'_}",
             measured = self.measured,
             atempts = self.atempts,
             sc_rate = self.success as f32 / dsc,
             success = self.success,
             sc_total = self.success + self.failure,
-            avg_time = self.total_time / dmeasured,
+        )?;
+
+        if let Some(c) = &self.output_check {
+            let same = c.checked() - c.mismatches();
+            if c.mismatches() == 0 {
+                writemcln!(
+                    f,
+                    color,
+                    "{'dg}Output : {'g bold}consistent {'_ dg}({same}/{})\
+                    {'_}",
+                    c.checked()
+                )?;
+            } else {
+                writemcln!(
+                    f,
+                    color,
+                    "{'dr}Output : {'r bold}inconsistent {'_ dr}({same}/{})\
+                    {'_}",
+                    c.checked()
+                )?;
+            }
+        }
+
+        let est = self.estimates();
+        let dur = |v: f64| Duration::from_nanos(v as u64);
+        let time_ci = |e: Option<Estimate>| {
+            e.map(|e| format!(" ± {:?}", dur(e.half_width())))
+                .unwrap_or_default()
+        };
+        let mem_ci = |e: Option<Estimate>| {
+            e.map(|e| {
+                format!(" ± {}", get_mem_string(e.half_width() as usize))
+            })
+            .unwrap_or_default()
+        };
+        let median_time = est.time_median.map(|e| dur(e.value));
+        let median_memory = est.memory_median.map(|e| e.value as usize);
+
+        writemcln!(
+            f,
+            color,
+            "
+{'dm}Avg time   : {'m bold}{avg_time:?}{avg_time_ci} {'_ dm}({conf}% CI, \
+{total_time:?}/{measured}){'_}
+{'dm}Median time: {'m bold}{median_time:?}{median_time_ci}{'_}
+{'dm}Best time  : {'m bold}{best_time:?}{'_}
+{'dm}Worst time : {'m bold}{worst_time:?}{'_}
+
+{'dc}Avg memory   : {'c bold}{avg_memory}{avg_memory_ci} {'_ dc}({conf}% CI, \
+{total_memory}/{measured}){'_}
+{'dc}Median memory: {'c bold}{median_memory}{median_memory_ci}{'_}
+{'dc}Best memory  : {'c bold}{best_memory}{'_}
+{'dc}Worst memory : {'c bold}{worst_memory}{'_}
+",
+            measured = self.measured,
+            conf = (est.confidence * 1e4).round() / 1e2,
+            avg_time = self.avg_time(),
+            avg_time_ci = time_ci(est.time_mean),
             total_time = self.total_time,
+            median_time = median_time.unwrap_or_default(),
+            median_time_ci = time_ci(est.time_median),
             best_time = self.best_time,
             worst_time = self.worst_time,
             avg_memory = get_mem_string(self.total_memory / dmem),
+            avg_memory_ci = mem_ci(est.memory_mean),
             total_memory = get_mem_string(self.total_memory),
+            median_memory = get_mem_string(median_memory.unwrap_or_default()),
+            median_memory_ci = mem_ci(est.memory_median),
             best_memory = get_mem_string(self.best_memory),
             worst_memory = get_mem_string(self.worst_memory),
         )?;
@@ -0,0 +1,248 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    io::{self, IsTerminal},
+};
+
+use termal::{formatmc, writemcln};
+
+use crate::{
+    json::JsonObject,
+    samples::Samples,
+    stats::{self, TestResult},
+};
+
+/// Comparison of single quantity (time or memory) of two sample sets.
+#[derive(Debug, Clone)]
+pub struct QuantityComparison {
+    /// Relative change of the mean (`0.03` means 3% more).
+    pub change: f64,
+    /// Welch's t-test. The effect size is Cohen's d.
+    pub welch: Option<TestResult>,
+    /// Mann-Whitney U test. The effect size is rank-biserial correlation.
+    pub mann_whitney: TestResult,
+    /// Whether the difference is statistically significant.
+    pub significant: bool,
+    /// Whether there are too few samples for Mann-Whitney U test to reach
+    /// the significance level. Welch's t-test decides in that case.
+    pub small_sample: bool,
+}
+
+/// Comparison of new samples against base samples.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    /// Name of the comparison (e.g. `new vs base`).
+    pub name: String,
+    /// Significance level.
+    pub alpha: f64,
+    pub time: Option<QuantityComparison>,
+    pub memory: Option<QuantityComparison>,
+}
+
+impl QuantityComparison {
+    /// Compare the samples. The difference is significant if Mann-Whitney U
+    /// test rejects the null hypothesis at the given significance level. If
+    /// there are too few samples for it to ever reject, Welch's t-test is
+    /// used instead.
+    pub fn new(base: &[f64], new: &[f64], alpha: f64) -> Option<Self> {
+        let mann_whitney = stats::mann_whitney_u(base, new)?;
+        let welch = stats::welch_t_test(base, new);
+
+        // The smallest p-value is achieved when the samples don't overlap.
+        let (nb, nn) = (base.len(), new.len());
+        let low: Vec<_> = (0..nb).map(|v| v as f64).collect();
+        let high: Vec<_> = (nb..nb + nn).map(|v| v as f64).collect();
+        let small_sample = stats::mann_whitney_u(&low, &high)
+            .is_none_or(|r| r.p_value >= alpha);
+
+        let significant = if small_sample {
+            welch.is_some_and(|w| w.p_value < alpha)
+        } else {
+            mann_whitney.p_value < alpha
+        };
+        Some(Self {
+            change: stats::mean(new) / stats::mean(base) - 1.,
+            welch,
+            mann_whitney,
+            significant,
+            small_sample,
+        })
+    }
+
+    pub fn to_json(&self) -> JsonObject {
+        JsonObject::new()
+            .field("change", self.change)
+            .field("welch_t", self.welch.map(|w| w.statistic))
+            .field("welch_p", self.welch.map(|w| w.p_value))
+            .field("cohen_d", self.welch.map(|w| w.effect))
+            .field("mann_whitney_u", self.mann_whitney.statistic)
+            .field("mann_whitney_p", self.mann_whitney.p_value)
+            .field("rank_biserial", self.mann_whitney.effect)
+            .field("significant", self.significant)
+            .field("small_sample", self.small_sample)
+    }
+
+    fn to_csv(&self) -> String {
+        let opt = |v: Option<f64>| {
+            v.filter(|v| v.is_finite())
+                .map(|v| v.to_string())
+                .unwrap_or_default()
+        };
+        format!(
+            "{},{},{},{},{},{},{},{}",
+            self.change,
+            opt(self.welch.map(|w| w.statistic)),
+            opt(self.welch.map(|w| w.p_value)),
+            opt(self.welch.map(|w| w.effect)),
+            self.mann_whitney.statistic,
+            self.mann_whitney.p_value,
+            self.mann_whitney.effect,
+            self.significant,
+        )
+    }
+}
+
+impl Comparison {
+    /// Compare `new` samples against `base` samples with the given
+    /// significance level.
+    pub fn new(
+        name: String,
+        base: &Samples,
+        new: &Samples,
+        alpha: f64,
+    ) -> Self {
+        Self {
+            name,
+            alpha,
+            time: QuantityComparison::new(
+                &base.times_ns(),
+                &new.times_ns(),
+                alpha,
+            ),
+            memory: QuantityComparison::new(
+                &base.memories_f64(),
+                &new.memories_f64(),
+                alpha,
+            ),
+        }
+    }
+
+    pub fn to_json(&self) -> JsonObject {
+        JsonObject::new()
+            .field("name", &self.name)
+            .field("alpha", self.alpha)
+            .field("time", self.time.as_ref().map(|c| c.to_json()))
+            .field("memory", self.memory.as_ref().map(|c| c.to_json()))
+    }
+
+    pub const CSV_HEADER: &str = "comparison,quantity,change,welch_t,welch_p,\
+        cohen_d,mann_whitney_u,mann_whitney_p,rank_biserial,significant";
+
+    /// Header of the columns given by [`Self::to_csv_columns`].
+    pub const CSV_COLUMNS: &str = "time_change,time_welch_t,time_welch_p,\
+        time_cohen_d,time_mann_whitney_u,time_mann_whitney_p,\
+        time_rank_biserial,time_significant,memory_change,memory_welch_t,\
+        memory_welch_p,memory_cohen_d,memory_mann_whitney_u,\
+        memory_mann_whitney_p,memory_rank_biserial,memory_significant";
+
+    /// Get the comparison as columns that can be appended to the csv row of
+    /// the new results. Quantities without enough samples are empty.
+    pub fn to_csv_columns(&self) -> String {
+        let col = |c: &Option<QuantityComparison>| match c {
+            Some(c) => c.to_csv(),
+            None => ",".repeat(7),
+        };
+        format!("{},{}", col(&self.time), col(&self.memory))
+    }
+
+    /// Get the comparison as csv rows (one for time and one for memory).
+    pub fn to_csv(&self, name: &str) -> String {
+        let mut res = String::new();
+        for (q, c) in [("time", &self.time), ("memory", &self.memory)] {
+            if let Some(c) = c {
+                res += &format!("{name},{q},{}\n", c.to_csv());
+            }
+        }
+        res
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let color = f
+            .precision()
+            .map(|p| p != 0)
+            .unwrap_or_else(|| io::stderr().is_terminal());
+
+        if f.sign_minus() {
+            writemcln!(
+                f,
+                color,
+                "{'gr}=============<< {'y}mproc comparison {'gr}>>============={'_}"
+            )?;
+        }
+
+        writemcln!(f, color, "{'w bold}{}{'_}", self.name)?;
+
+        for (label, c) in [("Time  ", &self.time), ("Memory", &self.memory)] {
+            let Some(c) = c else {
+                writemcln!(f, color, "{'dm}{label}: {'_}not enough samples")?;
+                continue;
+            };
+
+            let dir = match c.change.partial_cmp(&0.) {
+                Some(Ordering::Greater) => "more",
+                Some(Ordering::Less) => "less",
+                _ => "change",
+            };
+            let verdict = if !c.significant {
+                formatmc!(color, "{'y bold}within noise{'_}")
+            } else if c.change > 0. {
+                formatmc!(color, "{'r bold}significant{'_}")
+            } else {
+                formatmc!(color, "{'g bold}significant{'_}")
+            };
+            writemcln!(
+                f,
+                color,
+                "{'dm}{label}: {'m bold}{:.2}% {dir} {'_}{verdict}",
+                c.change.abs() * 100.
+            )?;
+
+            if let Some(w) = c.welch {
+                writemcln!(
+                    f,
+                    color,
+                    "{'dm}  Welch's t-test: {'_}p = {:.4}{'dm}, \
+                    Cohen's d = {'_}{:.3}",
+                    w.p_value,
+                    w.effect
+                )?;
+            }
+            writemcln!(
+                f,
+                color,
+                "{'dm}  Mann-Whitney U: {'_}p = {:.4}{'dm}, \
+                rank-biserial = {'_}{:.3}",
+                c.mann_whitney.p_value,
+                c.mann_whitney.effect
+            )?;
+            if c.small_sample && c.welch.is_some() {
+                writemcln!(
+                    f,
+                    color,
+                    "{'y}  Too few runs for Mann-Whitney U to be significant, \
+                    Welch's t-test decides.{'_}"
+                )?;
+            } else if c.small_sample {
+                writemcln!(
+                    f,
+                    color,
+                    "{'y}  Too few runs to detect significant difference.{'_}"
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,446 @@
+mod toml;
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    cli::{DurationArg, MemoryArg},
+    com_measure::ComMeasure,
+    err::{Error, Result},
+    get_dur_string, get_mem_string,
+};
+
+use self::toml::{Table, Value};
+
+/// Name of the project configuration file in the current directory.
+const PROJECT_FILE: &str = "mproc.toml";
+
+/// How value of configuration option is converted to flags.
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    /// Boolean flag. `false` gives the given flag that disables it.
+    Bool(&'static str),
+    /// Flag without negation. `true` gives the flag, `false` nothing.
+    Switch,
+    /// Flag with single value.
+    Value,
+    /// Flag with optional value given after `=`. `true` gives the flag
+    /// without value.
+    OptValue,
+    /// Flag followed by all the values of array.
+    Args,
+    /// Flag repeated for each value of array (or once for single value).
+    Each,
+    /// Flag repeated with `key=value` for each entry of table.
+    Pairs,
+}
+
+/// Options that can be set in the configuration files: key, the
+/// corresponding flag and how the value is converted.
+const OPTIONS: &[(&str, &str, Kind)] = &[
+    ("output", "--output", Kind::Value),
+    ("out", "--output", Kind::Value),
+    ("stdout", "--stdout", Kind::Switch),
+    ("stderr", "--stderr", Kind::Switch),
+    ("color", "--color", Kind::Value),
+    ("colour", "--color", Kind::Value),
+    (
+        "capture-stdout",
+        "--capture-stdout",
+        Kind::Bool("--no-capture-stdout"),
+    ),
+    (
+        "capture-stderr",
+        "--capture-stderr",
+        Kind::Bool("--no-capture-stderr"),
+    ),
+    ("capture-all", "--capture-all", Kind::Switch),
+    ("repeat", "--repeat", Kind::Value),
+    ("repeats", "--repeat", Kind::Value),
+    ("warmup", "--warmup", Kind::Value),
+    ("shell", "--shell", Kind::OptValue),
+    (
+        "calibrate-shell",
+        "--calibrate-shell",
+        Kind::Bool("--no-calibrate-shell"),
+    ),
+    ("format", "--format", Kind::Value),
+    ("parameter-scan", "--parameter-scan", Kind::Args),
+    ("step", "--step", Kind::Value),
+    ("parameter-list", "--parameter-list", Kind::Args),
+    ("setup", "--setup", Kind::Value),
+    ("prepare", "--prepare", Kind::Value),
+    ("cleanup", "--cleanup", Kind::Value),
+    ("input", "--input", Kind::Value),
+    ("stdin", "--stdin", Kind::Bool("--no-stdin")),
+    ("no-stdin", "--no-stdin", Kind::Bool("--stdin")),
+    ("stdout-to", "--stdout-to", Kind::Value),
+    ("stderr-to", "--stderr-to", Kind::Value),
+    (
+        "only-failed-output",
+        "--only-failed-output",
+        Kind::Bool("--no-only-failed-output"),
+    ),
+    (
+        "check-output",
+        "--check-output",
+        Kind::Bool("--no-check-output"),
+    ),
+    (
+        "check-stderr",
+        "--check-stderr",
+        Kind::Bool("--no-check-stderr"),
+    ),
+    ("expect-output", "--expect-output", Kind::Value),
+    ("expect-exit", "--expect-exit", Kind::Value),
+    (
+        "stop-on-failure",
+        "--stop-on-failure",
+        Kind::Bool("--no-stop-on-failure"),
+    ),
+    ("ignore-failure", "--ignore-failure", Kind::Switch),
+    ("env", "--env", Kind::Pairs),
+    ("unset-env", "--unset-env", Kind::Each),
+    ("clear-env", "--clear-env", Kind::Switch),
+    ("env-file", "--env-file", Kind::Each),
+    ("cwd", "--cwd", Kind::Value),
+    (
+        "tmpdir-per-run",
+        "--tmpdir-per-run",
+        Kind::Bool("--no-tmpdir-per-run"),
+    ),
+    ("cpus", "--cpus", Kind::Value),
+    ("nice", "--nice", Kind::Value),
+    ("sched", "--sched", Kind::Value),
+    ("order", "--order", Kind::Value),
+    ("seed", "--seed", Kind::Value),
+    ("min-runs", "--min-runs", Kind::Value),
+    ("max-runs", "--max-runs", Kind::Value),
+    ("target-ci", "--target-ci", Kind::Value),
+    ("max-time", "--max-time", Kind::Value),
+    ("confidence", "--confidence", Kind::Value),
+    ("save-samples", "--save-samples", Kind::Value),
+    ("compare", "--compare", Kind::Value),
+    ("histogram", "--histogram", Kind::Bool("--no-histogram")),
+    ("progress", "--progress", Kind::Bool("--no-progress")),
+    ("no-progress", "--no-progress", Kind::Bool("--progress")),
+    ("tui", "--tui", Kind::Bool("--no-tui")),
+    ("plot", "--plot", Kind::Value),
+    ("html", "--html", Kind::Value),
+    ("verbose", "--verbose", Kind::Bool("--no-verbose")),
+    ("strict", "--strict", Kind::Bool("--no-strict")),
+    ("record", "--record", Kind::OptValue),
+];
+
+/// Configuration loaded from the user and project configuration files.
+#[derive(Debug, Default)]
+pub struct Config {
+    /// Default flags as command line arguments.
+    pub defaults: Vec<String>,
+    pub benchmarks: Vec<Benchmark>,
+}
+
+/// Benchmark defined in configuration file.
+#[derive(Debug, Clone)]
+pub struct Benchmark {
+    pub name: String,
+    pub program: String,
+    pub args: Vec<String>,
+    /// Flags of the benchmark as command line arguments.
+    pub flags: Vec<String>,
+    /// Maximum allowed average time.
+    pub max_time: Option<Duration>,
+    /// Maximum allowed peak memory.
+    pub max_memory: Option<usize>,
+}
+
+impl Config {
+    /// Load the user configuration and the project configuration. Project
+    /// configuration takes precedence. Missing files are ignored.
+    pub fn load() -> Result<Self> {
+        let mut res = Self::default();
+        for path in [user_path(), Some(PathBuf::from(PROJECT_FILE))]
+            .into_iter()
+            .flatten()
+        {
+            if let Some(cfg) = Self::load_file(&path)? {
+                res.merge(cfg);
+            }
+        }
+        Ok(res)
+    }
+
+    /// Load configuration from the given file. Returns [`None`] if the file
+    /// doesn't exist.
+    pub fn load_file(path: &Path) -> Result<Option<Self>> {
+        let path_str = path.to_string_lossy().into_owned();
+        let data = match fs::read_to_string(path) {
+            Ok(d) => d,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(Error::FailedToOpen(path_str, e)),
+        };
+
+        let doc = toml::parse(&data).map_err(|(line, e)| {
+            Error::InvalidConfig(path_str.clone(), format!("line {line}: {e}"))
+        })?;
+        Self::from_table(doc)
+            .map(Some)
+            .map_err(|e| Error::InvalidConfig(path_str, e))
+    }
+
+    /// Load the benchmarks from suite file. The suite file has the same
+    /// format as the configuration files. Defaults of the suite are added to
+    /// the flags of each of its benchmarks.
+    pub fn load_suite(path: &str) -> Result<Vec<Benchmark>> {
+        let Some(Self {
+            defaults,
+            benchmarks,
+        }) = Self::load_file(Path::new(path))?
+        else {
+            return Err(Error::FailedToOpen(
+                path.to_string(),
+                io::ErrorKind::NotFound.into(),
+            ));
+        };
+        Ok(benchmarks
+            .into_iter()
+            .map(|mut b| {
+                b.flags.splice(0..0, defaults.iter().cloned());
+                b
+            })
+            .collect())
+    }
+
+    /// Get the benchmark with the given name.
+    pub fn benchmark(&self, name: &str) -> Result<&Benchmark> {
+        self.benchmarks
+            .iter()
+            .find(|b| b.name == name)
+            .ok_or_else(|| Error::UndefinedBenchmark(name.to_string()))
+    }
+
+    fn from_table(doc: Table) -> std::result::Result<Self, String> {
+        let mut res = Self::default();
+        for (key, value) in doc {
+            match (key.as_str(), value) {
+                ("defaults", Value::Table(t)) => {
+                    res.defaults = flags("defaults", &t)?
+                }
+                ("bench", Value::Table(t)) => {
+                    for (name, b) in t {
+                        let Value::Table(b) = b else {
+                            return Err(format!(
+                                "`bench.{name}` is not table."
+                            ));
+                        };
+                        res.benchmarks.push(Benchmark::from_table(name, b)?);
+                    }
+                }
+                (k, _) => return Err(format!("Unknown key `{k}`.")),
+            }
+        }
+        Ok(res)
+    }
+
+    /// Merge the other configuration into this. The other configuration
+    /// takes precedence.
+    fn merge(&mut self, other: Self) {
+        self.defaults.extend(other.defaults);
+        for b in other.benchmarks {
+            match self.benchmarks.iter_mut().find(|o| o.name == b.name) {
+                Some(o) => *o = b,
+                None => self.benchmarks.push(b),
+            }
+        }
+    }
+}
+
+impl Benchmark {
+    /// Check the results against the thresholds of the benchmark. Returns
+    /// description of each exceeded threshold.
+    pub fn check(&self, cm: &ComMeasure) -> Vec<String> {
+        let mut res = vec![];
+        if let Some(max) = self.max_time
+            && cm.avg_time() > max
+        {
+            res.push(format!(
+                "Average time {} exceeds {}.",
+                get_dur_string(cm.avg_time()),
+                get_dur_string(max)
+            ));
+        }
+        if let Some(max) = self.max_memory
+            && let Some(mem) = cm.worst_memory()
+            && mem > max
+        {
+            res.push(format!(
+                "Peak memory {} exceeds {}.",
+                get_mem_string(mem),
+                get_mem_string(max)
+            ));
+        }
+        res
+    }
+
+    fn from_table(
+        name: String,
+        mut table: Table,
+    ) -> std::result::Result<Self, String> {
+        let mut take = |key: &str| {
+            let idx = table.iter().position(|(k, _)| k == key)?;
+            Some(table.remove(idx).1)
+        };
+
+        let program = match take("command") {
+            Some(Value::String(c)) => c,
+            Some(_) => {
+                return Err(format!("`bench.{name}.command` must be string."));
+            }
+            None => return Err(format!("`bench.{name}` has no `command`.")),
+        };
+        let args = match take("args") {
+            Some(Value::Array(a)) => a
+                .iter()
+                .map(|v| scalar(&format!("bench.{name}.args"), v))
+                .collect::<std::result::Result<_, _>>()?,
+            Some(_) => {
+                return Err(format!("`bench.{name}.args` must be array."));
+            }
+            None => vec![],
+        };
+
+        let mut max_time = None;
+        let mut max_memory = None;
+        match take("thresholds") {
+            Some(Value::Table(t)) => {
+                for (k, v) in t {
+                    let v =
+                        scalar(&format!("bench.{name}.thresholds.{k}"), &v)?;
+                    let invalid = |_| {
+                        format!("Invalid {k} threshold `{v}` of `{name}`.")
+                    };
+                    match k.as_str() {
+                        "time" => {
+                            max_time = Some(
+                                v.parse::<DurationArg>().map_err(invalid)?.0,
+                            )
+                        }
+                        "memory" => {
+                            max_memory = Some(
+                                v.parse::<MemoryArg>().map_err(invalid)?.0,
+                            )
+                        }
+                        _ => {
+                            return Err(format!(
+                                "Unknown threshold `{k}` of `{name}`. \
+                                Expected `time` or `memory`."
+                            ));
+                        }
+                    }
+                }
+            }
+            Some(_) => {
+                return Err(format!(
+                    "`bench.{name}.thresholds` must be table."
+                ));
+            }
+            None => {}
+        }
+
+        Ok(Self {
+            flags: flags(&format!("bench.{name}"), &table)?,
+            name,
+            program,
+            args,
+            max_time,
+            max_memory,
+        })
+    }
+}
+
+/// Convert table of options to command line flags. `path` is the path of the
+/// table used in error messages. The options are converted as described by
+/// [`OPTIONS`].
+fn flags(
+    path: &str,
+    table: &Table,
+) -> std::result::Result<Vec<String>, String> {
+    let mut res = vec![];
+    for (k, v) in table {
+        let key = format!("{path}.{k}");
+        let Some(&(_, flag, kind)) = OPTIONS.iter().find(|(o, _, _)| o == k)
+        else {
+            return Err(format!("Unknown key `{key}`."));
+        };
+        let flag = flag.to_string();
+        match (kind, v) {
+            (
+                Kind::Bool(_) | Kind::Switch | Kind::OptValue,
+                Value::Bool(true),
+            ) => res.push(flag),
+            (Kind::Bool(neg), Value::Bool(false)) => res.push(neg.to_string()),
+            (Kind::Switch, Value::Bool(false)) => {}
+            (Kind::Bool(_) | Kind::Switch, _) => {
+                return Err(format!("`{key}` must be boolean."));
+            }
+            (Kind::OptValue, v) => {
+                res.push(format!("{flag}={}", scalar(&key, v)?))
+            }
+            (Kind::Value, v) => {
+                res.push(flag);
+                res.push(scalar(&key, v)?);
+            }
+            (Kind::Args, Value::Array(a)) => {
+                res.push(flag);
+                for v in a {
+                    res.push(scalar(&key, v)?);
+                }
+            }
+            (Kind::Args, _) => return Err(format!("`{key}` must be array.")),
+            (Kind::Each, Value::Array(a)) => {
+                for v in a {
+                    res.push(flag.clone());
+                    res.push(scalar(&key, v)?);
+                }
+            }
+            (Kind::Each, v) => {
+                res.push(flag);
+                res.push(scalar(&key, v)?);
+            }
+            (Kind::Pairs, Value::Table(t)) => {
+                for (tk, tv) in t {
+                    res.push(flag.clone());
+                    res.push(format!("{tk}={}", scalar(&key, tv)?));
+                }
+            }
+            (Kind::Pairs, _) => return Err(format!("`{key}` must be table.")),
+        }
+    }
+    Ok(res)
+}
+
+/// Convert value to argument.
+fn scalar(key: &str, v: &Value) -> std::result::Result<String, String> {
+    match v {
+        Value::String(s) => Ok(s.clone()),
+        Value::Integer(i) => Ok(i.to_string()),
+        Value::Float(f) => Ok(f.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        Value::Array(_) | Value::Table(_) => {
+            Err(format!("Invalid value of `{key}`. Expected single value."))
+        }
+    }
+}
+
+/// Get the path to the user configuration file.
+fn user_path() -> Option<PathBuf> {
+    let var = |name: &str| env::var_os(name).filter(|v| !v.is_empty());
+    let base = var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| var("HOME").map(|h| PathBuf::from(h).join(".config")))
+        .or_else(|| var("APPDATA").map(PathBuf::from))?;
+    Some(base.join("mproc").join("config.toml"))
+}
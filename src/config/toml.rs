@@ -0,0 +1,469 @@
+/// Value in TOML document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Array(Vec<Value>),
+    Table(Table),
+}
+
+/// Table with the keys in the order in which they were defined.
+pub type Table = Vec<(String, Value)>;
+
+/// Parse the subset of TOML used by the configuration files. Multiline
+/// strings, dates and arrays of tables are not supported. On error returns
+/// the line and description of the error.
+pub fn parse(s: &str) -> Result<Table, (usize, String)> {
+    let mut parser = Parser { s, pos: 0 };
+    parser.document().map_err(|e| {
+        let line = s[..parser.pos.min(s.len())].matches('\n').count() + 1;
+        (line, e)
+    })
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+type PResult<T> = Result<T, String>;
+
+impl Parser<'_> {
+    fn document(&mut self) -> PResult<Table> {
+        let mut root = Table::new();
+        let mut path: Vec<String> = vec![];
+        loop {
+            self.skip_blank(true);
+            match self.peek() {
+                None => return Ok(root),
+                Some('[') => {
+                    self.pos += 1;
+                    if self.peek() == Some('[') {
+                        return Err(
+                            "Arrays of tables are not supported.".to_string()
+                        );
+                    }
+                    path = self.key()?;
+                    self.expect(']')?;
+                    table_at(&mut root, &path)?;
+                }
+                Some(_) => {
+                    let key = self.key()?;
+                    self.expect('=')?;
+                    let value = self.value()?;
+                    let (name, parents) = key.split_last().unwrap();
+                    let full: Vec<_> =
+                        path.iter().chain(parents).cloned().collect();
+                    let table = table_at(&mut root, &full)?;
+                    if table.iter().any(|(k, _)| k == name) {
+                        return Err(format!("Duplicate key `{name}`."));
+                    }
+                    table.push((name.clone(), value));
+                }
+            }
+            self.line_end()?;
+        }
+    }
+
+    /// Parse dotted key.
+    fn key(&mut self) -> PResult<Vec<String>> {
+        let mut res = vec![];
+        loop {
+            self.skip_blank(false);
+            let part = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while self.peek().is_some_and(|c| {
+                        c.is_ascii_alphanumeric() || c == '_' || c == '-'
+                    }) {
+                        self.pos += 1;
+                    }
+                    if start == self.pos {
+                        return Err("Expected key.".to_string());
+                    }
+                    self.s[start..self.pos].to_string()
+                }
+            };
+            res.push(part);
+            self.skip_blank(false);
+            if self.peek() != Some('.') {
+                return Ok(res);
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self) -> PResult<Value> {
+        self.skip_blank(false);
+        match self.peek() {
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some(_) => self.scalar(),
+            None => Err("Expected value.".to_string()),
+        }
+    }
+
+    fn array(&mut self) -> PResult<Value> {
+        self.pos += 1;
+        let mut res = vec![];
+        loop {
+            self.skip_blank(true);
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Value::Array(res));
+            }
+            res.push(self.value()?);
+            self.skip_blank(true);
+            match self.next() {
+                Some(',') => {}
+                Some(']') => return Ok(Value::Array(res)),
+                _ => return Err("Expected `,` or `]` in array.".to_string()),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> PResult<Value> {
+        self.pos += 1;
+        let mut res = Table::new();
+        self.skip_blank(false);
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Value::Table(res));
+        }
+        loop {
+            let key = self.key()?;
+            self.expect('=')?;
+            let value = self.value()?;
+            let (name, parents) = key.split_last().unwrap();
+            let table = table_at(&mut res, parents)?;
+            if table.iter().any(|(k, _)| k == name) {
+                return Err(format!("Duplicate key `{name}`."));
+            }
+            table.push((name.clone(), value));
+            self.skip_blank(false);
+            match self.next() {
+                Some(',') => {}
+                Some('}') => return Ok(Value::Table(res)),
+                _ => {
+                    return Err(
+                        "Expected `,` or `}` in inline table.".to_string()
+                    );
+                }
+            }
+        }
+    }
+
+    /// Parse boolean or number.
+    fn scalar(&mut self) -> PResult<Value> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || "+-._".contains(c))
+        {
+            self.pos += 1;
+        }
+        let v = &self.s[start..self.pos];
+        let num = v.replace('_', "");
+        let is_float = !num.starts_with("0x")
+            && (num.contains('.') || num.contains(['e', 'E']));
+        match v {
+            "true" => Ok(Value::Bool(true)),
+            "false" => Ok(Value::Bool(false)),
+            "" => Err("Expected value.".to_string()),
+            _ if is_float => num
+                .parse()
+                .map(Value::Float)
+                .map_err(|_| format!("Invalid number `{v}`.")),
+            _ => num
+                .parse()
+                .map(Value::Integer)
+                .map_err(|_| format!("Invalid value `{v}`.")),
+        }
+    }
+
+    fn basic_string(&mut self) -> PResult<String> {
+        self.pos += 1;
+        if self.s[self.pos..].starts_with("\"\"") {
+            return Err("Multiline strings are not supported.".to_string());
+        }
+        let mut res = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(res),
+                Some('\\') => res.push(self.escape()?),
+                Some('\n') | None => {
+                    return Err("Unterminated string.".to_string());
+                }
+                Some(c) => res.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> PResult<char> {
+        let hex = |p: &mut Self, len: usize| {
+            let code =
+                p.s.get(p.pos..p.pos + len)
+                    .and_then(|h| u32::from_str_radix(h, 16).ok())
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| "Invalid unicode escape.".to_string())?;
+            p.pos += len;
+            Ok(code)
+        };
+        match self.next() {
+            Some('b') => Ok('\u{8}'),
+            Some('t') => Ok('\t'),
+            Some('n') => Ok('\n'),
+            Some('f') => Ok('\u{c}'),
+            Some('r') => Ok('\r'),
+            Some('e') => Ok('\u{1b}'),
+            Some('"') => Ok('"'),
+            Some('\\') => Ok('\\'),
+            Some('u') => hex(self, 4),
+            Some('U') => hex(self, 8),
+            _ => Err("Invalid escape sequence.".to_string()),
+        }
+    }
+
+    fn literal_string(&mut self) -> PResult<String> {
+        self.pos += 1;
+        if self.s[self.pos..].starts_with("''") {
+            return Err("Multiline strings are not supported.".to_string());
+        }
+        let start = self.pos;
+        loop {
+            match self.next() {
+                Some('\'') => return Ok(self.s[start..self.pos - 1].into()),
+                Some('\n') | None => {
+                    return Err("Unterminated string.".to_string());
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// Skip whitespace and comments. With `newlines` also newlines are
+    /// skipped.
+    fn skip_blank(&mut self, newlines: bool) {
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' => self.pos += 1,
+                '\r' | '\n' if newlines => self.pos += 1,
+                '#' => {
+                    let len = self.s[self.pos..].find('\n');
+                    self.pos = len.map_or(self.s.len(), |l| self.pos + l);
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn line_end(&mut self) -> PResult<()> {
+        self.skip_blank(false);
+        match self.next() {
+            Some('\n') | None => Ok(()),
+            Some('\r') if self.next() == Some('\n') => Ok(()),
+            _ => Err("Expected end of line.".to_string()),
+        }
+    }
+
+    fn expect(&mut self, c: char) -> PResult<()> {
+        self.skip_blank(false);
+        if self.next() == Some(c) {
+            Ok(())
+        } else {
+            Err(format!("Expected `{c}`."))
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.s[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+}
+
+/// Get the table at the given path. Missing tables are created.
+fn table_at<'a>(
+    mut table: &'a mut Table,
+    path: &[String],
+) -> PResult<&'a mut Table> {
+    for name in path {
+        let idx = match table.iter().position(|(k, _)| k == name) {
+            Some(i) => i,
+            None => {
+                table.push((name.clone(), Value::Table(vec![])));
+                table.len() - 1
+            }
+        };
+        match &mut table[idx].1 {
+            Value::Table(t) => table = t,
+            _ => return Err(format!("Key `{name}` is not a table.")),
+        }
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(v: &str) -> Value {
+        Value::String(v.to_string())
+    }
+
+    fn t(entries: &[(&str, Value)]) -> Value {
+        Value::Table(
+            entries
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect(),
+        )
+    }
+
+    fn err(doc: &str) -> (usize, String) {
+        parse(doc).expect_err("parse should fail")
+    }
+
+    #[test]
+    fn test_scalars() {
+        let doc = parse(
+            "a = 1_000\nb = -42\nc = 0.5\nd = 1e3\ne = true\nf = false\n",
+        )
+        .unwrap();
+        assert_eq!(
+            doc,
+            vec![
+                ("a".into(), Value::Integer(1000)),
+                ("b".into(), Value::Integer(-42)),
+                ("c".into(), Value::Float(0.5)),
+                ("d".into(), Value::Float(1000.)),
+                ("e".into(), Value::Bool(true)),
+                ("f".into(), Value::Bool(false)),
+            ]
+        );
+        assert!(err("a = 12x").1.contains("Invalid value"));
+        assert!(err("a = 1.2.3").1.contains("Invalid number"));
+    }
+
+    #[test]
+    fn test_strings() {
+        let doc = parse(
+            r#"a = "tab\there \"q\" \\ \u00e9 \U0001F600"
+b = 'C:\path\n'
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            doc,
+            vec![
+                ("a".into(), s("tab\there \"q\" \\ é 😀")),
+                ("b".into(), s(r"C:\path\n")),
+            ]
+        );
+        assert!(err(r#"a = "\q""#).1.contains("escape"));
+        assert!(err(r#"a = "\uZZZZ""#).1.contains("unicode"));
+        assert!(err("a = \"open\nb = 1").1.contains("Unterminated"));
+    }
+
+    #[test]
+    fn test_tables_and_dotted_keys() {
+        let doc = parse(
+            "top = 1\n[bench.sort]\ncommand = 'sort'\nenv.LC_ALL = 'C'\n\
+            [defaults]\n\"quoted key\" = 2\n",
+        )
+        .unwrap();
+        assert_eq!(
+            doc,
+            vec![
+                ("top".into(), Value::Integer(1)),
+                (
+                    "bench".into(),
+                    t(&[(
+                        "sort",
+                        t(&[
+                            ("command", s("sort")),
+                            ("env", t(&[("LC_ALL", s("C"))])),
+                        ])
+                    )])
+                ),
+                ("defaults".into(), t(&[("quoted key", Value::Integer(2))])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inline_tables_and_arrays() {
+        let doc = parse(
+            "env = { A = '1', b.c = 2 }\nempty = {}\n\
+            args = [\n  'x', # first\n  # comment\n  [1, 2],\n]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            doc,
+            vec![
+                (
+                    "env".into(),
+                    t(&[("A", s("1")), ("b", t(&[("c", Value::Integer(2))]))])
+                ),
+                ("empty".into(), t(&[])),
+                (
+                    "args".into(),
+                    Value::Array(vec![
+                        s("x"),
+                        Value::Array(vec![
+                            Value::Integer(1),
+                            Value::Integer(2)
+                        ]),
+                    ])
+                ),
+            ]
+        );
+        assert!(err("a = [1 2]").1.contains("array"));
+        assert!(err("a = { b = 1 c = 2 }").1.contains("inline table"));
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        assert_eq!(err("a = 1\na = 2\n"), (2, "Duplicate key `a`.".into()));
+        assert!(err("[t]\nx = 1\n[t]\nx = 2").1.contains("Duplicate"));
+        assert!(err("a = { b = 1, b = 2 }").1.contains("Duplicate"));
+        assert!(err("a = 1\na.b = 2").1.contains("not a table"));
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert!(err("a = \"\"\"x\"\"\"").1.contains("Multiline"));
+        assert!(err("a = '''x'''").1.contains("Multiline"));
+        assert!(err("[[bench]]\n").1.contains("Arrays of tables"));
+    }
+
+    #[test]
+    fn test_error_lines() {
+        assert_eq!(err("a = 1\n\n# c\nb = ").0, 4);
+        assert_eq!(err("a = 1 b = 2\nc = 3").0, 1);
+        assert!(err("a = 1 b = 2").1.contains("end of line"));
+    }
+
+    #[test]
+    fn test_crlf_and_comments() {
+        let doc =
+            parse("# head\r\na = 1 # note\r\n\r\n[t]\r\nb = 'x'\r\n").unwrap();
+        assert_eq!(
+            doc,
+            vec![
+                ("a".into(), Value::Integer(1)),
+                ("t".into(), t(&[("b", s("x"))])),
+            ]
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    fs::File,
+    fs::{self, File},
     process::{Child, Command, ExitStatus},
 };
 
@@ -16,17 +16,76 @@ pub enum Error {
     )]
     #[cfg(target_os = "linux")]
     ProcessTooFast,
+    #[error("Failed to open file `{0}`: {1}")]
+    FailedToOpen(String, std::io::Error),
+    #[error(
+        "Invalid line {1} in environment file `{0}`. Expected `KEY=VALUE`."
+    )]
+    InvalidEnvFile(String, usize),
+    #[error(
+        "Invalid line {1} in samples file `{0}`. Expected `TIME_NS,MEMORY`."
+    )]
+    InvalidSamplesFile(String, usize),
+    #[error("Invalid line {1} in history file `{0}`.")]
+    InvalidHistory(String, usize),
+    #[error(
+        "Cannot determine location of the history. Set `XDG_DATA_HOME` or \
+        `HOME`."
+    )]
+    NoHistoryPath,
+    #[error("There is no recorded history of `{0}`.")]
+    UnknownBenchmark(String),
+    #[error("`{0}` has no session {1}. There are {2} sessions.")]
+    InvalidSession(String, usize, usize),
+    #[error(
+        "`{0}` has only one recorded session. At least two are needed to \
+        diff."
+    )]
+    SingleSession(String),
+    #[error("Invalid configuration file `{0}`: {1}")]
+    InvalidConfig(String, String),
+    #[error("Benchmark `{0}` is not defined in the configuration files.")]
+    UndefinedBenchmark(String),
+    #[error("There are no benchmarks defined in the configuration files.")]
+    NoBenchmarks,
+    #[error(
+        "{0} of {1} benchmarks failed, exceeded their thresholds or regressed."
+    )]
+    SuiteFailed(usize, usize),
+    #[error("Failed to create directory `{0}`: {1}")]
+    FailedToCreateDir(String, std::io::Error),
     #[error("Failed to write to file `{0}`: {1}")]
     FailedToWrite(String, std::io::Error),
     #[error("Failed to spawn the process `{0}`: {1}")]
     FailedToSpawn(String, std::io::Error),
     #[error("Failed to wait for the process `{0}`: {1}")]
     FailedToWait(String, std::io::Error),
+    #[error("The command `{0}` failed with {1}.")]
+    CommandFailed(String, ExitStatus),
+    #[error("Stopped because run {0} failed.")]
+    StoppedOnFailure(usize),
+    #[error("Stopped because the last {0} runs failed.")]
+    TooManyFailures(usize),
     #[error("Failed to get process memory: {0}")]
     FailedToGetMemory(anyhow::Error),
     #[error("Failed to get process time: {0}")]
     #[cfg(target_os = "windows")]
     FailedToGetTime(anyhow::Error),
+    #[error("CPU index {0} is too large.")]
+    #[cfg(target_os = "linux")]
+    InvalidCpu(usize),
+    #[error("The option {0} is not supported on this platform.")]
+    #[cfg(not(target_os = "linux"))]
+    Unsupported(&'static str),
+    #[error(
+        "The system is too noisy for reliable measurement. See the warnings \
+        above."
+    )]
+    NoisySystem,
+    #[error("The option {0} requires stderr to be terminal.")]
+    NotTerminal(&'static str),
+    #[error(transparent)]
+    Termal(#[from] termal::Error),
     #[error(transparent)]
     Pareg(#[from] pareg::ArgError),
     #[error(transparent)]
@@ -49,6 +108,14 @@ pub fn file_create(p: &str) -> Result<File> {
     File::create(p).map_err(|e| Error::FailedToWrite(p.to_string(), e))
 }
 
+pub fn file_write(p: &str, content: &str) -> Result<()> {
+    fs::write(p, content).map_err(|e| Error::FailedToWrite(p.to_string(), e))
+}
+
+pub fn file_open(p: &str) -> Result<File> {
+    File::open(p).map_err(|e| Error::FailedToOpen(p.to_string(), e))
+}
+
 pub fn cmd_spawn(cmd: &mut Command) -> Result<Child> {
     cmd.spawn().map_err(|e| {
         Error::FailedToSpawn(
@@ -66,3 +133,14 @@ pub fn child_wait(child: &mut Child, cmd: &Command) -> Result<ExitStatus> {
         )
     })
 }
+
+pub fn cmd_run(mut cmd: Command, name: &str) -> Result<()> {
+    let status = cmd_spawn(&mut cmd)?
+        .wait()
+        .map_err(|e| Error::FailedToWait(name.to_string(), e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::CommandFailed(name.to_string(), status))
+    }
+}
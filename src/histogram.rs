@@ -0,0 +1,140 @@
+use termal::formatmc;
+
+use crate::stats;
+
+/// Maximum number of bins of the histogram.
+const MAX_BINS: usize = 20;
+/// Width of the longest bar in characters.
+const BAR_WIDTH: usize = 40;
+/// Characters used to draw fractions of bar cell in eights.
+const BAR_PARTS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Histogram of measured values.
+#[derive(Debug)]
+pub struct Histogram {
+    start: f64,
+    bin_width: f64,
+    counts: Vec<usize>,
+    outliers: Vec<usize>,
+    mean: f64,
+    median: f64,
+}
+
+impl Histogram {
+    /// Create histogram of the values. Returns [`None`] if there are no
+    /// values. Values outside 1.5 IQR from the quartiles are outliers.
+    pub fn new(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+
+        let mut sorted = values.to_vec();
+        let median = stats::median(&mut sorted);
+        let q1 = stats::percentile(&sorted, 0.25);
+        let q3 = stats::percentile(&sorted, 0.75);
+        let fence = (q3 - q1) * 1.5;
+        let (lo, hi) = (q1 - fence, q3 + fence);
+
+        let start = sorted[0];
+        let end = sorted[sorted.len() - 1];
+        // Sturges' rule
+        let bins = ((values.len() as f64).log2().ceil() as usize + 1)
+            .clamp(1, MAX_BINS);
+        let bins = if end == start { 1 } else { bins };
+        let bin_width = (end - start) / bins as f64;
+
+        let mut counts = vec![0; bins];
+        let mut outliers = vec![0; bins];
+        for v in &sorted {
+            let i = Self::bin_of(start, bin_width, bins, *v);
+            counts[i] += 1;
+            if *v < lo || *v > hi {
+                outliers[i] += 1;
+            }
+        }
+
+        Some(Self {
+            start,
+            bin_width,
+            counts,
+            outliers,
+            mean: stats::mean(values),
+            median,
+        })
+    }
+
+    fn bin_of(start: f64, width: f64, bins: usize, v: f64) -> usize {
+        if width == 0. {
+            0
+        } else {
+            (((v - start) / width) as usize).min(bins - 1)
+        }
+    }
+
+    /// Render the histogram. `fmt` is used to format values and `bar` is
+    /// the termal color of the bars.
+    pub fn render(
+        &self,
+        title: &str,
+        color: bool,
+        bar: &str,
+        fmt: impl Fn(f64) -> String,
+    ) -> String {
+        let bins = self.counts.len();
+        let labels: Vec<_> = (0..bins)
+            .map(|i| fmt(self.start + self.bin_width * i as f64))
+            .collect();
+        let label_width =
+            labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let max = self.counts.iter().copied().max().unwrap_or(0).max(1);
+        let count_width = max.to_string().len();
+        let mean_bin =
+            Self::bin_of(self.start, self.bin_width, bins, self.mean);
+        let median_bin =
+            Self::bin_of(self.start, self.bin_width, bins, self.median);
+        let bar = if color { bar } else { "" };
+
+        let mut res = formatmc!(color, "{'w bold}{title}{'_}\n");
+        for (i, label) in labels.iter().enumerate() {
+            let mut markers = vec![];
+            if i == mean_bin {
+                markers.push(formatmc!(color, "{'m}mean{'_}"));
+            }
+            if i == median_bin {
+                markers.push(formatmc!(color, "{'c}median{'_}"));
+            }
+            match self.outliers[i] {
+                0 => {}
+                1 => markers.push(formatmc!(color, "{'r}1 outlier{'_}")),
+                n => markers.push(formatmc!(color, "{'r}{n} outliers{'_}")),
+            }
+            let markers = if markers.is_empty() {
+                String::new()
+            } else {
+                format!(" ← {}", markers.join(", "))
+            };
+
+            res += &formatmc!(
+                color,
+                "{'dm}{label:>label_width$} │{'_}{bar}{}{'_} \
+                {:>count_width$}{markers}\n",
+                Self::bar(self.counts[i], max),
+                self.counts[i],
+            );
+        }
+        res
+    }
+
+    /// Draw bar of the given length relative to `max`. The result has always
+    /// [`BAR_WIDTH`] characters.
+    fn bar(count: usize, max: usize) -> String {
+        let eights = count * BAR_WIDTH * 8 / max;
+        let full = eights / 8;
+        let mut res = "█".repeat(full);
+        if full < BAR_WIDTH {
+            res.push(BAR_PARTS[eights % 8]);
+            res.extend(std::iter::repeat_n(' ', BAR_WIDTH - full - 1));
+        }
+        res
+    }
+}
@@ -0,0 +1,361 @@
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    com_measure::{ComMeasure, MeasureOpts, RunRecord},
+    err::{Error, Result},
+    metadata::Metadata,
+    stats,
+};
+
+/// Minimal relative step of the results that is reported as change.
+const MIN_STEP: f64 = 0.03;
+
+/// Single recorded measurement session.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub name: String,
+    /// Time of the recording in seconds since the unix epoch.
+    pub time: u64,
+    pub command: String,
+    pub metadata: Metadata,
+    pub runs: Vec<RunRecord>,
+}
+
+/// Step change in the results of the benchmark.
+#[derive(Debug, Clone, Copy)]
+pub struct ChangePoint {
+    /// 1 based index of the first session after the change.
+    pub session: usize,
+    /// `time` or `memory`.
+    pub quantity: &'static str,
+    /// Relative change of the level (`0.1` means 10% more).
+    pub change: f64,
+}
+
+/// All the recorded sessions loaded from the history store.
+#[derive(Debug, Default)]
+pub struct History {
+    sessions: Vec<Session>,
+}
+
+impl Session {
+    /// Create new session recorded now.
+    pub fn new(
+        name: String,
+        command: String,
+        metadata: Metadata,
+        runs: Vec<RunRecord>,
+    ) -> Self {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Self {
+            name,
+            time,
+            command,
+            metadata,
+            runs,
+        }
+    }
+
+    /// Get the statistics of the recorded runs.
+    pub fn stats(&self) -> ComMeasure {
+        let mut res = ComMeasure::new(MeasureOpts::default(), None);
+        for r in &self.runs {
+            res.add_record(*r);
+        }
+        res
+    }
+
+    /// Get the short form of the git commit of the session.
+    pub fn commit(&self) -> Option<&str> {
+        self.metadata
+            .git_commit
+            .as_deref()
+            .map(|c| &c[..c.len().min(10)])
+    }
+
+    /// Get the time of the recording as `YYYY-MM-DD HH:MM` in UTC.
+    pub fn date(&self) -> String {
+        let days = (self.time / 86400) as i64;
+        let mins = self.time % 86400 / 60;
+        // Conversion from days to civil date by Howard Hinnant.
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let d = doy - (153 * mp + 2) / 5 + 1;
+        let m = if mp < 10 { mp + 3 } else { mp - 9 };
+        let y = yoe + era * 400 + (m <= 2) as i64;
+        format!("{y}-{m:02}-{d:02} {:02}:{:02}", mins / 60, mins % 60)
+    }
+
+    fn write(&self, out: &mut String) {
+        *out += &format!("session\t{}\n", escape(&self.name));
+        *out += &format!("time\t{}\n", self.time);
+        *out += &format!("command\t{}\n", escape(&self.command));
+        for (k, v) in self.metadata.fields(false) {
+            *out += &format!("meta\t{k}\t{}\n", escape(&v));
+        }
+        for r in &self.runs {
+            let opt = |v: Option<String>| v.unwrap_or_else(|| "-".to_string());
+            *out += &format!(
+                "run\t{}\t{}\t{}\t{}\n",
+                r.time.as_nanos(),
+                opt(r.memory.map(|m| m.to_string())),
+                opt(r.exit_code.map(|c| c.to_string())),
+                r.success,
+            );
+        }
+        *out += "end\n";
+    }
+}
+
+impl History {
+    /// Load the history. If there is no history yet, it is empty.
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        let data = match fs::read_to_string(&path) {
+            Ok(d) => d,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self::default());
+            }
+            Err(e) => {
+                return Err(Error::FailedToOpen(
+                    path.to_string_lossy().into_owned(),
+                    e,
+                ));
+            }
+        };
+
+        let mut sessions = vec![];
+        let mut cur: Option<(Session, Vec<(String, String)>)> = None;
+        for (i, line) in data.lines().enumerate() {
+            let err = || {
+                Error::InvalidHistory(
+                    path.to_string_lossy().into_owned(),
+                    i + 1,
+                )
+            };
+            let fields: Vec<_> = line.split('\t').map(unescape).collect();
+            match (fields[0].as_str(), &mut cur) {
+                ("session", _) => {
+                    let name = fields.get(1).ok_or_else(err)?.clone();
+                    let session = Session {
+                        name,
+                        time: 0,
+                        command: String::new(),
+                        metadata: Metadata::default(),
+                        runs: vec![],
+                    };
+                    cur = Some((session, vec![]));
+                }
+                ("time", Some((s, _))) => {
+                    s.time = fields
+                        .get(1)
+                        .and_then(|t| t.parse().ok())
+                        .ok_or_else(err)?;
+                }
+                ("command", Some((s, _))) => {
+                    s.command = fields.get(1).ok_or_else(err)?.clone();
+                }
+                ("meta", Some((_, meta))) => {
+                    let [_, k, v] = fields.as_slice() else {
+                        return Err(err());
+                    };
+                    meta.push((k.clone(), v.clone()));
+                }
+                ("run", Some((s, _))) => {
+                    s.runs.push(parse_run(&fields).ok_or_else(err)?);
+                }
+                ("end", Some(_)) => {
+                    let (mut s, meta) = cur.take().unwrap();
+                    s.metadata = Metadata::from_fields(
+                        meta.iter().map(|(k, v)| (k.as_str(), v.as_str())),
+                    );
+                    sessions.push(s);
+                }
+                ("", _) => {}
+                _ => return Err(err()),
+            }
+        }
+
+        // Unfinished session at the end (e.g. interrupted write) is ignored.
+        Ok(Self { sessions })
+    }
+
+    /// Append the session to the history store.
+    pub fn append(session: &Session) -> Result<()> {
+        let path = Self::path()?;
+        let path_str = path.to_string_lossy().into_owned();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| {
+                Error::FailedToCreateDir(dir.to_string_lossy().into_owned(), e)
+            })?;
+        }
+
+        let mut data = String::new();
+        session.write(&mut data);
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut f| f.write_all(data.as_bytes()))
+            .map_err(|e| Error::FailedToWrite(path_str, e))
+    }
+
+    /// Get the names of the recorded benchmarks with the number of sessions
+    /// and the last session.
+    pub fn names(&self) -> Vec<(&str, usize, &Session)> {
+        let mut res: Vec<(&str, usize, &Session)> = vec![];
+        for s in &self.sessions {
+            match res.iter_mut().find(|r| r.0 == s.name) {
+                Some(r) => {
+                    r.1 += 1;
+                    r.2 = s;
+                }
+                None => res.push((&s.name, 1, s)),
+            }
+        }
+        res
+    }
+
+    /// Get all the sessions of the benchmark from the oldest. Fails if there
+    /// are no sessions with the name.
+    pub fn sessions(&self, name: &str) -> Result<Vec<&Session>> {
+        let res: Vec<_> =
+            self.sessions.iter().filter(|s| s.name == name).collect();
+        if res.is_empty() {
+            Err(Error::UnknownBenchmark(name.to_string()))
+        } else {
+            Ok(res)
+        }
+    }
+
+    /// Get the path to the history store.
+    fn path() -> Result<PathBuf> {
+        let var = |name: &str| env::var_os(name).filter(|v| !v.is_empty());
+        let base = var("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                var("HOME").map(|h| PathBuf::from(h).join(".local/share"))
+            })
+            .or_else(|| var("APPDATA").map(PathBuf::from))
+            .ok_or(Error::NoHistoryPath)?;
+        Ok(base.join("mproc").join("history"))
+    }
+}
+
+/// Detect step changes of the average time and peak memory of the sessions.
+/// The sessions are given with their 1 based index.
+pub fn change_points(sessions: &[(usize, &Session)]) -> Vec<ChangePoint> {
+    let stats: Vec<_> = sessions.iter().map(|(_, s)| s.stats()).collect();
+    let times: Vec<_> = sessions
+        .iter()
+        .zip(&stats)
+        .map(|((i, _), cm)| (*i, cm.avg_time().as_nanos() as f64))
+        .collect();
+    let memories: Vec<_> = sessions
+        .iter()
+        .zip(&stats)
+        .filter_map(|((i, _), cm)| Some((*i, cm.worst_memory()? as f64)))
+        .collect();
+
+    let mut res = quantity_changes("time", &times);
+    res.extend(quantity_changes("memory", &memories));
+    res.sort_by_key(|c| c.session);
+    res
+}
+
+fn quantity_changes(
+    quantity: &'static str,
+    series: &[(usize, f64)],
+) -> Vec<ChangePoint> {
+    let values: Vec<_> = series.iter().map(|s| s.1).collect();
+    let mut bounds = stats::change_points(&values, MIN_STEP);
+    bounds.insert(0, 0);
+    bounds.push(values.len());
+
+    bounds
+        .windows(3)
+        .map(|b| {
+            let before = stats::mean(&values[b[0]..b[1]]);
+            let after = stats::mean(&values[b[1]..b[2]]);
+            ChangePoint {
+                session: series[b[1]].0,
+                quantity,
+                change: after / before - 1.,
+            }
+        })
+        .collect()
+}
+
+/// Get the session with the given 1 based index from the sessions.
+pub fn session<'a>(
+    sessions: &[&'a Session],
+    idx: usize,
+) -> Result<&'a Session> {
+    idx.checked_sub(1)
+        .and_then(|i| sessions.get(i))
+        .copied()
+        .ok_or_else(|| {
+            Error::InvalidSession(
+                sessions[0].name.clone(),
+                idx,
+                sessions.len(),
+            )
+        })
+}
+
+fn parse_run(fields: &[String]) -> Option<RunRecord> {
+    let [_, time, memory, exit_code, success] = fields else {
+        return None;
+    };
+    fn opt<T: FromStr>(v: &str) -> Option<Option<T>> {
+        if v == "-" {
+            Some(None)
+        } else {
+            v.parse().ok().map(Some)
+        }
+    }
+
+    Some(RunRecord {
+        time: Duration::from_nanos(time.parse().ok()?),
+        memory: opt(memory)?,
+        exit_code: opt(exit_code)?,
+        success: success.parse().ok()?,
+    })
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+fn unescape(s: &str) -> String {
+    let mut res = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            res.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => res.push('\t'),
+            Some('n') => res.push('\n'),
+            Some(c) => res.push(c),
+            None => res.push('\\'),
+        }
+    }
+    res
+}
@@ -0,0 +1,334 @@
+use std::{mem, time::Duration};
+
+use crate::{
+    cli::Parameter,
+    com_measure::ComMeasure,
+    comparison::{Comparison, QuantityComparison},
+    err::{Result, file_write},
+    get_dur_string, get_mem_string,
+    json::{JsonObject, ToJson},
+    measurement::Measurement,
+    metadata::Metadata,
+    plot::{self, escape},
+    stats::Estimate,
+};
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em auto; max-width: 900px;
+    color: #222; }
+h1 { color: #9c3fb8; }
+h2 { border-bottom: 1px solid #ccc; padding-bottom: .2em; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { padding: .3em .8em; border-bottom: 1px solid #eee;
+    text-align: right; }
+th { background: #f4f4f4; }
+td:first-child, th:first-child { text-align: left; }
+code { background: #f4f4f4; padding: .1em .3em; }
+details { margin: .5em 0; }
+summary { cursor: pointer; font-weight: bold; }
+";
+
+/// Self-contained HTML report of the measurement session. The measured data
+/// are embedded in the report as JSON.
+#[derive(Debug, Default)]
+pub struct Report {
+    info: Vec<(String, String)>,
+    body: String,
+    data: JsonObject,
+}
+
+impl Report {
+    /// Create report of measurement of the given command. `md` describes
+    /// the host of the measurement.
+    pub fn new(command: &str, md: &Metadata) -> Self {
+        let mut res = Self::default();
+        res.info("Command", &format!("<code>{}</code>", escape(command)));
+        for (k, v) in md.fields(true) {
+            res.info(k, &escape(&v));
+        }
+        res.data("command", command);
+        res.data("metadata", md.to_json());
+        res
+    }
+
+    /// Add results of single run.
+    pub fn measurement(
+        &mut self,
+        m: &Measurement,
+        curve: &[(Duration, usize)],
+    ) {
+        let mut rows = vec![["Time".to_string(), get_dur_string(m.time)]];
+        rows.push([
+            "Memory".to_string(),
+            m.memory
+                .as_ref()
+                .map_or_else(|e| e.to_string(), |m| get_mem_string(*m)),
+        ]);
+        rows.push([
+            "Exit code".to_string(),
+            m.exit_code.map_or("none".to_string(), |c| c.to_string()),
+        ]);
+        if let Some(o) = m.output_matches {
+            rows.push(["Output matches".to_string(), o.to_string()]);
+        }
+
+        self.section("Summary");
+        self.table(&["", "Value"], &rows);
+        self.section("Memory over time");
+        self.body += &plot::single(curve, m);
+        self.data("result", m.to_json());
+    }
+
+    /// Add results of repeated runs.
+    pub fn com_measure(&mut self, cm: &ComMeasure) {
+        self.section("Summary");
+        self.summary(&[("", cm)]);
+        self.section("Distribution");
+        self.body += &plot::repeated(cm);
+        self.section("Runs");
+        self.runs(cm);
+        self.data("result", cm.to_json());
+    }
+
+    /// Add results of parameter sweep.
+    pub fn sweep(&mut self, param: &Parameter, res: &[(String, ComMeasure)]) {
+        self.info(
+            "Parameter",
+            &format!("<code>{}</code>", escape(param.name())),
+        );
+
+        self.section("Summary");
+        let named: Vec<_> = res
+            .iter()
+            .map(|(v, cm)| (format!("{}={v}", param.name()), cm))
+            .collect();
+        let named: Vec<_> =
+            named.iter().map(|(n, cm)| (n.as_str(), *cm)).collect();
+        self.summary(&named);
+        self.body += &plot::sweep(param, res);
+
+        self.section("Variants");
+        for (name, cm) in named {
+            self.body +=
+                &format!("<details><summary>{}</summary>\n", escape(name));
+            self.body += &plot::repeated(cm);
+            self.runs(cm);
+            self.body += "</details>\n";
+        }
+
+        let results: Vec<_> = res
+            .iter()
+            .map(|(v, cm)| {
+                JsonObject::new()
+                    .field("value", v)
+                    .field("stats", cm.to_json())
+            })
+            .collect();
+        self.data("parameter", param.name());
+        self.data("results", results);
+    }
+
+    /// Add comparisons of the results.
+    pub fn comparisons(&mut self, comparisons: &[Comparison]) {
+        if comparisons.is_empty() {
+            return;
+        }
+
+        self.section("Comparisons");
+        let mut rows = vec![];
+        for c in comparisons {
+            for (q, qc) in [("time", &c.time), ("memory", &c.memory)] {
+                rows.push(comparison_row(&c.name, q, qc.as_ref()));
+            }
+        }
+        self.table(
+            &[
+                "Comparison",
+                "Quantity",
+                "Change",
+                "Welch's t-test p",
+                "Mann-Whitney U p",
+                "Result",
+            ],
+            &rows,
+        );
+
+        let json: Vec<_> = comparisons.iter().map(|c| c.to_json()).collect();
+        self.data("comparisons", json);
+    }
+
+    /// Write the report to the given file.
+    pub fn save(self, path: &str) -> Result<()> {
+        let mut info = String::new();
+        for (name, value) in &self.info {
+            info += &format!("<tr><th>{name}</th><td>{value}</td></tr>\n");
+        }
+        let html = format!(
+            "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>mproc report</title>
+<style>{STYLE}</style>
+</head>
+<body>
+<h1>mproc report</h1>
+<table>
+{info}</table>
+{}<script type=\"application/json\" id=\"mproc-data\">{}</script>
+</body>
+</html>
+",
+            self.body,
+            self.data.to_json().replace("</", "<\\/"),
+        );
+        file_write(path, &html)
+    }
+
+    /// Add row to the table with information about the session. `value` is
+    /// HTML.
+    fn info(&mut self, name: &str, value: &str) {
+        self.info.push((name.to_string(), value.to_string()));
+    }
+
+    fn data(&mut self, key: &str, value: impl ToJson) {
+        self.data = mem::take(&mut self.data).field(key, value);
+    }
+
+    fn section(&mut self, title: &str) {
+        self.body += &format!("<h2>{}</h2>\n", escape(title));
+    }
+
+    /// Add table with summary of the results.
+    fn summary(&mut self, res: &[(&str, &ComMeasure)]) {
+        let rows: Vec<_> = res
+            .iter()
+            .map(|(name, cm)| {
+                let est = cm.estimates();
+                [
+                    name.to_string(),
+                    format!("{}/{}", cm.success(), cm.attempts()),
+                    with_ci(
+                        get_dur_string(cm.avg_time()),
+                        est.time_mean,
+                        time_str,
+                    ),
+                    est.time_median.map_or(String::new(), |e| {
+                        with_ci(time_str(e.value), Some(e), time_str)
+                    }),
+                    cm.best_time().map_or(String::new(), get_dur_string),
+                    cm.worst_time().map_or(String::new(), get_dur_string),
+                    with_ci(
+                        cm.avg_memory().map_or(String::new(), get_mem_string),
+                        est.memory_mean,
+                        mem_str,
+                    ),
+                    cm.worst_memory().map_or(String::new(), get_mem_string),
+                ]
+            })
+            .collect();
+        self.table(
+            &[
+                "",
+                "Success",
+                "Avg time",
+                "Median time",
+                "Best time",
+                "Worst time",
+                "Avg memory",
+                "Worst memory",
+            ],
+            &rows,
+        );
+    }
+
+    /// Add table with the individual runs.
+    fn runs(&mut self, cm: &ComMeasure) {
+        let rows: Vec<_> = cm
+            .runs()
+            .iter()
+            .enumerate()
+            .map(|(i, r)| {
+                [
+                    (i + 1).to_string(),
+                    get_dur_string(r.time),
+                    r.memory.map_or(String::new(), get_mem_string),
+                    r.exit_code.map_or("none".to_string(), |c| c.to_string()),
+                    if r.success { "yes" } else { "no" }.to_string(),
+                ]
+            })
+            .collect();
+        self.table(&["Run", "Time", "Memory", "Exit code", "Success"], &rows);
+    }
+
+    /// Add table. Cells are text.
+    fn table<const N: usize>(
+        &mut self,
+        header: &[&str],
+        rows: &[[String; N]],
+    ) {
+        self.body += "<table>\n<tr>";
+        for h in header {
+            self.body += &format!("<th>{}</th>", escape(h));
+        }
+        self.body += "</tr>\n";
+        for row in rows {
+            self.body += "<tr>";
+            for cell in row {
+                self.body += &format!("<td>{}</td>", escape(cell));
+            }
+            self.body += "</tr>\n";
+        }
+        self.body += "</table>\n";
+    }
+}
+
+fn comparison_row(
+    name: &str,
+    quantity: &str,
+    c: Option<&QuantityComparison>,
+) -> [String; 6] {
+    let Some(c) = c else {
+        return [
+            name.to_string(),
+            quantity.to_string(),
+            String::new(),
+            String::new(),
+            String::new(),
+            "not enough samples".to_string(),
+        ];
+    };
+    let verdict = if c.significant {
+        "significant"
+    } else {
+        "within noise"
+    };
+    [
+        name.to_string(),
+        quantity.to_string(),
+        format!("{:+.2}%", c.change * 100.),
+        c.welch
+            .map_or(String::new(), |w| format!("{:.4}", w.p_value)),
+        format!("{:.4}", c.mann_whitney.p_value),
+        verdict.to_string(),
+    ]
+}
+
+fn with_ci(
+    value: String,
+    est: Option<Estimate>,
+    fmt: fn(f64) -> String,
+) -> String {
+    match est {
+        Some(e) => format!("{value} ± {}", fmt(e.half_width())),
+        None => value,
+    }
+}
+
+fn time_str(ns: f64) -> String {
+    get_dur_string(Duration::from_nanos(ns as u64))
+}
+
+fn mem_str(b: f64) -> String {
+    get_mem_string(b as usize)
+}
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+/// Type that can be written as json value.
+pub trait ToJson {
+    fn write_json(&self, out: &mut String);
+
+    fn to_json(&self) -> String {
+        let mut res = String::new();
+        self.write_json(&mut res);
+        res
+    }
+}
+
+/// Json object that is created field by field.
+#[derive(Debug, Default)]
+pub struct JsonObject {
+    data: String,
+}
+
+impl JsonObject {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn field(mut self, key: &str, value: impl ToJson) -> Self {
+        if !self.data.is_empty() {
+            self.data.push(',');
+        }
+        key.write_json(&mut self.data);
+        self.data.push(':');
+        value.write_json(&mut self.data);
+        self
+    }
+}
+
+impl ToJson for JsonObject {
+    fn write_json(&self, out: &mut String) {
+        out.push('{');
+        *out += &self.data;
+        out.push('}');
+    }
+}
+
+impl ToJson for str {
+    fn write_json(&self, out: &mut String) {
+        out.push('"');
+        for c in self.chars() {
+            match c {
+                '"' => *out += "\\\"",
+                '\\' => *out += "\\\\",
+                '\n' => *out += "\\n",
+                '\r' => *out += "\\r",
+                '\t' => *out += "\\t",
+                c if (c as u32) < 0x20 => {
+                    *out += &format!("\\u{:04x}", c as u32)
+                }
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+}
+
+impl ToJson for String {
+    fn write_json(&self, out: &mut String) {
+        self.as_str().write_json(out);
+    }
+}
+
+impl ToJson for f64 {
+    fn write_json(&self, out: &mut String) {
+        if self.is_finite() {
+            *out += &self.to_string();
+        } else {
+            *out += "null";
+        }
+    }
+}
+
+impl ToJson for bool {
+    fn write_json(&self, out: &mut String) {
+        *out += if *self { "true" } else { "false" };
+    }
+}
+
+/// Durations are written as number of nanoseconds.
+impl ToJson for Duration {
+    fn write_json(&self, out: &mut String) {
+        self.as_nanos().write_json(out);
+    }
+}
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            Some(v) => v.write_json(out),
+            None => *out += "null",
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn write_json(&self, out: &mut String) {
+        out.push('[');
+        for (i, v) in self.iter().enumerate() {
+            if i != 0 {
+                out.push(',');
+            }
+            v.write_json(out);
+        }
+        out.push(']');
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn write_json(&self, out: &mut String) {
+        self.as_slice().write_json(out);
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn write_json(&self, out: &mut String) {
+        (*self).write_json(out);
+    }
+}
+
+macro_rules! impl_to_json_num {
+    ($($t:ty),*) => {
+        $(impl ToJson for $t {
+            fn write_json(&self, out: &mut String) {
+                *out += &self.to_string();
+            }
+        })*
+    };
+}
+
+impl_to_json_num!(i32, i64, u32, u64, u128, usize);
@@ -1,19 +1,63 @@
-use cli::{Args, Output, print_help};
-use com_measure::ComMeasure;
-use err::Result;
+use cli::{
+    Args, HistoryCmd, Output, OutputFormat, OutputType, RunCmd, RunOrder,
+    print_help,
+};
+use com_measure::{ComMeasure, MeasureOpts};
+use comparison::Comparison;
+use config::{Benchmark, Config};
+use err::{
+    Error, Result, child_wait, cmd_run, cmd_spawn, file_open, file_write,
+};
+use history::{History, Session};
+use html::Report;
 use measurement::Measurement;
+use metadata::Metadata;
+use noise::{CpuSampler, LoadMonitor};
+use output_check::OutputCheck;
 use pareg::Pareg;
+use proc_sample::ProcSample;
+use progress::Progress;
+use rng::Rng;
+use run_output::RunOutput;
+use samples::Samples;
 use std::{
-    iter,
+    collections::VecDeque,
+    fs,
+    io::{self, IsTerminal},
+    iter, mem,
+    path::Path,
     process::{Command, ExitCode, Stdio},
-    time::Duration,
+    time::{Duration, Instant},
 };
+use suite::{Status, SuiteResult};
 use termal::eprintmcln;
+use tmp_dir::TmpDir;
+use tui::Dashboard;
 
 mod cli;
 mod com_measure;
+mod comparison;
+mod config;
 mod err;
+mod histogram;
+mod history;
+mod html;
+mod json;
 mod measurement;
+mod metadata;
+mod noise;
+mod output_check;
+mod plot;
+mod proc_sample;
+mod progress;
+mod rng;
+mod run_output;
+mod samples;
+mod sched;
+mod stats;
+mod suite;
+mod tmp_dir;
+mod tui;
 
 fn main() -> ExitCode {
     match start() {
@@ -29,6 +73,14 @@ fn start() -> Result<()> {
     let mut args = Args::parse(Pareg::args())?;
     args.output.validate()?;
 
+    if let Some(cmd) = args.history.take() {
+        return history(cmd, args);
+    }
+
+    if let Some(cmd) = args.run.take() {
+        return run_benchmarks(cmd, args);
+    }
+
     if args.program.is_none() {
         if !args.helped {
             eprintmcln!(
@@ -40,16 +92,128 @@ fn start() -> Result<()> {
         return Ok(());
     };
 
-    if args.repeat == 0 {
+    measure(args).map(|_| ())
+}
+
+/// Measure the program with the setup and cleanup. Returns the results with
+/// the name of the variant (empty if there are no variants).
+fn measure(args: Args) -> Result<Vec<(String, ComMeasure)>> {
+    if let Some(setup) = &args.setup {
+        cmd_run(shell_cmd(args.shell_name(), setup), setup)?;
+    }
+
+    let cleanup = args
+        .cleanup
+        .as_ref()
+        .map(|c| (shell_cmd(args.shell_name(), c), c.clone()));
+
+    let res = if args.parameter.is_some() {
+        measure_sweep(args)
+    } else if args.repeat == 0 && !args.is_adaptive() {
         measure_single(args)
     } else {
         measure_multiple(args)
+    };
+
+    if let Some((cmd, cleanup)) = cleanup {
+        let cres = cmd_run(cmd, &cleanup);
+        let res = res?;
+        cres.map(|_| res)
+    } else {
+        res
     }
 }
 
-pub fn prepare_cmd(program: &str, args: &Args) -> Command {
-    let mut cmd = Command::new(program);
-    cmd.args(&args.args);
+/// Prepare and measure single run of the program. Returns whether the run
+/// was successful.
+fn measure_run(
+    stats: &mut ComMeasure,
+    program: &str,
+    prog_args: &[String],
+    args: &Args,
+    run: usize,
+    dash: Option<&mut Dashboard>,
+) -> Result<bool> {
+    let (mut cmd, _tmp) = run_cmd(program, prog_args, args, run)?;
+    let files = prepare_run(&mut cmd, args, run)?;
+    let mut m = match dash {
+        Some(d) => {
+            let title = cmd_title(program, prog_args);
+            let m = d.measure(&mut cmd, &title, run, &stats.samples().times);
+            stats.add(m)?
+        }
+        None => stats.measure(&mut cmd)?,
+    };
+    finish_run(files, &mut m, stats.output_check_mut(), args)?;
+    m.memory.map(|_| m.success)
+}
+
+/// Get human readable description of the command.
+fn cmd_title(program: &str, prog_args: &[String]) -> String {
+    iter::once(program)
+        .chain(prog_args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Create command for the given run. Returns also the temporary directory of
+/// the run that is removed when dropped.
+fn run_cmd(
+    program: &str,
+    prog_args: &[String],
+    args: &Args,
+    run: usize,
+) -> Result<(Command, Option<TmpDir>)> {
+    if !args.tmpdir_per_run {
+        return Ok((prepare_cmd(program, prog_args, args)?, None));
+    }
+
+    let tmp = TmpDir::new(run)?;
+    let prog_args: Vec<_> =
+        prog_args.iter().map(|a| tmp.substitute(a)).collect();
+    let mut cmd = prepare_cmd(&tmp.substitute(program), &prog_args, args)?;
+    cmd.current_dir(tmp.path());
+    cmd.env("TMPDIR", tmp.path());
+    Ok((cmd, Some(tmp)))
+}
+
+/// Default minimum number of runs when the number of runs is adaptive.
+const MIN_ADAPTIVE_RUNS: usize = 3;
+/// Default maximum number of runs when the number of runs is adaptive and
+/// it is not limited by time.
+const MAX_ADAPTIVE_RUNS: usize = 1000;
+/// Number of failed runs in row after which adaptive measurement stops.
+const MAX_FAILURES_IN_ROW: usize = 10;
+
+/// Number of runs of empty command used to measure the shell overhead.
+const SHELL_CALIBRATION_RUNS: usize = 10;
+
+/// Maximum number of sessions shown in trend of the history.
+const TREND_SESSIONS: usize = 50;
+
+/// How often is memory sampled for the plot of single run.
+const PLOT_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Default confidence level of confidence intervals.
+pub const DEFAULT_CONFIDENCE: f64 = 0.95;
+
+pub fn prepare_cmd(
+    program: &str,
+    prog_args: &[String],
+    args: &Args,
+) -> Result<Command> {
+    let mut cmd = if let Some(shell) = &args.shell {
+        let mut script = program.to_string();
+        for a in prog_args {
+            script.push(' ');
+            script += &shell_quote(shell, a);
+        }
+        shell_cmd(shell, &script)
+    } else {
+        let mut cmd = Command::new(program);
+        cmd.args(prog_args);
+        cmd
+    };
 
     if args.capture_stdout {
         cmd.stdout(Stdio::null());
@@ -58,9 +222,214 @@ pub fn prepare_cmd(program: &str, args: &Args) -> Command {
     if args.capture_stderr {
         cmd.stderr(Stdio::null());
     }
+
+    if args.no_stdin || args.tui {
+        cmd.stdin(Stdio::null());
+    }
+
+    if args.tui {
+        cmd.stdout(Stdio::null());
+        cmd.stderr(Stdio::null());
+    }
+
+    if let Some(cwd) = &args.cwd {
+        cmd.current_dir(cwd);
+    }
+
+    for e in &args.env {
+        e.apply(&mut cmd)?;
+    }
+
+    sched::set_sched(&mut cmd, args.cpus.as_deref(), args.nice, args.sched)?;
+
+    Ok(cmd)
+}
+
+/// Files to which output of single run is redirected.
+#[derive(Default)]
+struct RunFiles {
+    stdout: Option<RunOutput>,
+    stderr: Option<RunOutput>,
+}
+
+/// Run the prepare command before run of the given command.
+fn run_prepare(cmd: &Command, args: &Args) -> Result<()> {
+    if let Some(prepare) = &args.prepare {
+        let mut hook = shell_cmd(args.shell_name(), prepare);
+        if let Some(dir) = cmd.get_current_dir() {
+            hook.current_dir(dir);
+        }
+        cmd_run(hook, prepare)?;
+    }
+    Ok(())
+}
+
+/// Prepare the command for the next run.
+fn prepare_run(
+    cmd: &mut Command,
+    args: &Args,
+    run: usize,
+) -> Result<RunFiles> {
+    run_prepare(cmd, args)?;
+
+    if let Some(input) = &args.input {
+        cmd.stdin(file_open(input)?);
+    }
+
+    let mut files = RunFiles::default();
+    let same_file =
+        args.stdout_to.is_some() && args.stdout_to == args.stderr_to;
+
+    let stdout = if let Some(path) = &args.stdout_to {
+        Some(RunOutput::open(path, run)?)
+    } else if args.check_output {
+        Some(RunOutput::temp("stdout")?)
+    } else {
+        None
+    };
+    if let Some((out, file)) = stdout {
+        if same_file {
+            cmd.stderr(file.try_clone()?);
+        }
+        cmd.stdout(file);
+        files.stdout = Some(out);
+    }
+
+    let stderr = if same_file {
+        None
+    } else if let Some(path) = &args.stderr_to {
+        Some(RunOutput::open(path, run)?)
+    } else if args.check_stderr {
+        Some(RunOutput::temp("stderr")?)
+    } else {
+        None
+    };
+    if let Some((out, file)) = stderr {
+        cmd.stderr(file);
+        files.stderr = Some(out);
+    }
+
+    Ok(files)
+}
+
+/// Cleanup after single run of the program and check its output.
+fn finish_run(
+    files: RunFiles,
+    m: &mut Measurement,
+    check: Option<&mut OutputCheck>,
+    args: &Args,
+) -> Result<()> {
+    if let Some(check) = check {
+        let stdout = match &files.stdout {
+            Some(o) => o.read()?,
+            None => vec![],
+        };
+        let stderr = match &files.stderr {
+            Some(o) if args.check_stderr => Some(o.read()?),
+            _ => None,
+        };
+        m.output_matches = Some(check.check(&stdout, stderr.as_deref()));
+    }
+
+    // Output that doesn't match is kept, because it is needed to find out
+    // what went wrong.
+    let discard = args.only_failed_output
+        && m.success
+        && m.output_matches != Some(false);
+    for o in [files.stdout, files.stderr].into_iter().flatten() {
+        if discard || o.is_temp() {
+            o.discard()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Run the program the number of warmup runs. The runs are not measured and
+/// their output is discarded.
+fn warmup(program: &str, prog_args: &[String], args: &Args) -> Result<()> {
+    for _ in 0..args.warmup {
+        let (mut cmd, _tmp) = run_cmd(program, prog_args, args, 0)?;
+        run_prepare(&cmd, args)?;
+        if let Some(input) = &args.input {
+            cmd.stdin(file_open(input)?);
+        }
+        cmd.stdout(Stdio::null()).stderr(Stdio::null());
+        let mut child = cmd_spawn(&mut cmd)?;
+        child_wait(&mut child, &cmd)?;
+    }
+    Ok(())
+}
+
+/// Create output check if output should be checked.
+fn output_check(args: &Args) -> Result<Option<OutputCheck>> {
+    if !args.check_output {
+        return Ok(None);
+    }
+
+    let expected = match &args.expect_output {
+        Some(path) => Some(
+            fs::read(path)
+                .map_err(|e| Error::FailedToOpen(path.clone(), e))?,
+        ),
+        None => None,
+    };
+    Ok(Some(OutputCheck::new(expected)))
+}
+
+pub fn shell_cmd(shell: &str, script: &str) -> Command {
+    let mut cmd = Command::new(shell);
+    if is_cmd(shell) {
+        cmd.arg("/C");
+    } else {
+        cmd.arg("-c");
+    }
+    cmd.arg(script);
     cmd
 }
 
+/// Quote the argument so that the shell passes it to the program unchanged.
+/// Arguments without special characters are kept as they are.
+fn shell_quote(shell: &str, arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-+=.,:/@%".contains(c));
+    if plain {
+        arg.to_string()
+    } else if is_cmd(shell) {
+        format!("\"{}\"", arg.replace('"', "\"\""))
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Check whether the shell is the windows `cmd`.
+fn is_cmd(shell: &str) -> bool {
+    Path::new(shell)
+        .file_stem()
+        .is_some_and(|s| s.eq_ignore_ascii_case("cmd"))
+}
+
+/// Measure how long it takes the shell to run empty command. Returns zero if
+/// shell is not used or calibration is disabled.
+fn shell_overhead(args: &Args) -> Result<Duration> {
+    let Some(shell) = &args.shell else {
+        return Ok(Duration::ZERO);
+    };
+    if !args.calibrate_shell {
+        return Ok(Duration::ZERO);
+    }
+
+    let mut cmd = shell_cmd(shell, "");
+    let mut stats = ComMeasure::new(MeasureOpts::default(), None);
+    for _ in 0..SHELL_CALIBRATION_RUNS {
+        stats.measure(&mut cmd)?;
+    }
+
+    Ok(stats.avg_time())
+}
+
 pub fn get_mem_string(mem: usize) -> String {
     const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB", "EiB", "PiB"];
 
@@ -137,24 +506,593 @@ pub fn duration_to_string(dur: Duration, trunc: bool) -> String {
     res
 }
 
-fn measure_single(args: Args) -> Result<()> {
+fn measure_single(args: Args) -> Result<Vec<(String, ComMeasure)>> {
     let program = args.program.as_deref().unwrap_or_default();
-    let mut cmd = prepare_cmd(program, &args);
-    let mut output = Output::new(args.output, args.color_mode);
+    warmup(program, &args.args, &args)?;
+    let (mut cmd, _tmp) = run_cmd(program, &args.args, &args, 1)?;
+    let overhead = shell_overhead(&args)?;
+    let mut check = output_check(&args)?;
+    let files = prepare_run(&mut cmd, &args, 1)?;
+    let mut md = Metadata::collect();
+    let mut curve = vec![];
+    let mut stats = if args.tui {
+        let mut dash = Dashboard::new(Some(1), args.color_mode.stderr())?;
+        let m =
+            dash.measure(&mut cmd, &cmd_title(program, &args.args), 1, &[])?;
+        curve = dash.memory_curve().to_vec();
+        m
+    } else if args.plot.is_some() || args.html.is_some() {
+        let start = Instant::now();
+        Measurement::measure_watched(&mut cmd, PLOT_INTERVAL, &mut |pid| {
+            if let Some(s) = ProcSample::get(pid) {
+                curve.push((start.elapsed(), s.rss));
+            }
+            true
+        })?
+    } else {
+        Measurement::measure(&mut cmd)?
+    };
+    md.finish();
+    stats.time = stats.time.saturating_sub(overhead);
+    stats.success = args.expect_exit.matches(stats.exit_code);
+    finish_run(files, &mut stats, check.as_mut(), &args)?;
 
-    let stats = Measurement::measure(&mut cmd)?;
-    output.print_measurement(&stats)
+    let mut output =
+        Output::new(args.output, args.color_mode, args.format, args.verbose);
+    output.print_measurement(&stats, &md)?;
+    if let Some(path) = &args.plot {
+        file_write(path, &plot::single(&curve, &stats))?;
+    }
+    if let Some(path) = &args.html {
+        let mut report = Report::new(&cmd_title(program, &args.args), &md);
+        report.measurement(&stats, &curve);
+        report.save(path)?;
+    }
+    if let Some(name) = &args.record {
+        let command = cmd_title(program, &args.args);
+        History::append(&Session::new(
+            record_name(name, &command),
+            command,
+            md,
+            vec![(&stats).into()],
+        ))?;
+    }
+
+    let mut res = ComMeasure::new(MeasureOpts::default(), None);
+    res.add_record((&stats).into());
+    Ok(vec![(String::new(), res)])
+}
+
+/// Get the maximum number of runs of the program if it is limited.
+fn max_runs(args: &Args) -> Option<usize> {
+    if !args.is_adaptive() {
+        Some(args.repeat)
+    } else if args.max_runs.is_some() {
+        args.max_runs
+    } else if args.repeat != 0 {
+        Some(args.repeat)
+    } else if args.max_time.is_some() {
+        None
+    } else if args.target_ci.is_some() {
+        Some(MAX_ADAPTIVE_RUNS)
+    } else {
+        Some(args.min_runs.unwrap_or(MIN_ADAPTIVE_RUNS))
+    }
+}
+
+/// Warn about conditions of the system that make the timings unreliable.
+/// With `--strict` the measurement is refused if there are any. The CPU
+/// usage of other processes is checked only if it was already sampled long
+/// enough (always with `--strict`). Otherwise the sampler is kept so that
+/// it can be checked after the measurement.
+fn check_noise(
+    args: &Args,
+    md: &Metadata,
+    sampler: &mut Option<CpuSampler>,
+) -> Result<()> {
+    let mut warnings = noise::check(md);
+    if let Some(s) = sampler.take_if(|s| args.strict || s.ready()) {
+        s.wait();
+        warnings.extend(s.warnings());
+    }
+    print_warnings(args, &warnings);
+    if args.strict && !warnings.is_empty() {
+        Err(Error::NoisySystem)
+    } else {
+        Ok(())
+    }
+}
+
+fn print_warnings(args: &Args, warnings: &[String]) {
+    for w in warnings {
+        eprintmcln!(args.color_mode.stderr(), "{'m}warning: {'_}{w}");
+    }
+}
+
+/// Get the name under which the results are recorded. Results without
+/// explicit name are recorded under the command.
+fn record_name(name: &str, command: &str) -> String {
+    if name.is_empty() {
+        command.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Run the history subcommand.
+fn history(cmd: HistoryCmd, mut args: Args) -> Result<()> {
+    let hist = History::load()?;
+    let out = mem::take(&mut args.output);
+    let mut output =
+        Output::new(out, args.color_mode, args.format, args.verbose);
+    match cmd {
+        HistoryCmd::List => output.print_history_list(&hist.names()),
+        HistoryCmd::Show { name, session } => {
+            let sessions = hist.sessions(&name)?;
+            let idx = session.unwrap_or(sessions.len());
+            output.print_session(history::session(&sessions, idx)?)
+        }
+        HistoryCmd::Diff {
+            name,
+            sessions: idx,
+        } => {
+            let sessions = hist.sessions(&name)?;
+            let (a, b) = match idx {
+                Some(idx) => idx,
+                None if sessions.len() < 2 => {
+                    return Err(Error::SingleSession(name));
+                }
+                None => (sessions.len() - 1, sessions.len()),
+            };
+            let base = history::session(&sessions, a)?;
+            let new = history::session(&sessions, b)?;
+            let cmp = Comparison::new(
+                format!("{name}: #{b} vs #{a}"),
+                base.stats().samples(),
+                new.stats().samples(),
+                significance(&args),
+            );
+            output.print_comparisons(&[cmp])
+        }
+        HistoryCmd::Trend { name } => {
+            let sessions = hist.sessions(&name)?;
+            let skip = sessions.len().saturating_sub(TREND_SESSIONS);
+            let shown: Vec<_> = sessions
+                .iter()
+                .enumerate()
+                .skip(skip)
+                .map(|(i, s)| (i + 1, *s))
+                .collect();
+            output.print_trend(&name, &shown, &history::change_points(&shown))
+        }
+    }
+}
+
+/// Run the benchmarks from the configuration files or suite file and print
+/// their combined results. Fails if any of the benchmarks failed, exceeded
+/// its thresholds or regressed against its last recorded session.
+fn run_benchmarks(cmd: RunCmd, mut args: Args) -> Result<()> {
+    if args.helped {
+        return Ok(());
+    }
+
+    let benches = match cmd {
+        RunCmd::All => args.config.benchmarks.clone(),
+        RunCmd::Names(names) => names
+            .iter()
+            .map(|n| args.config.benchmark(n).cloned())
+            .collect::<Result<_>>()?,
+        RunCmd::Suite(path) => Config::load_suite(&path)?,
+    };
+    if benches.is_empty() {
+        return Err(Error::NoBenchmarks);
+    }
+
+    // Results in machine readable format are only in the combined results.
+    let human = args.format == OutputFormat::Human;
+    let hist = History::load()?;
+    let color = args.color_mode.stderr();
+    let mut results = vec![];
+    for bench in &benches {
+        eprintmcln!(color, "{'g}Benchmark {'c}{}{'_}", bench.name);
+        match measure_benchmark(&mut args, bench, human) {
+            Ok(res) => {
+                for (variant, cm) in res {
+                    let name = if variant.is_empty() {
+                        bench.name.clone()
+                    } else {
+                        format!("{} {variant}", bench.name)
+                    };
+                    results.push(SuiteResult::new(
+                        name,
+                        bench,
+                        cm,
+                        &hist,
+                        significance(&args),
+                    ));
+                }
+            }
+            Err(e) => {
+                e.print();
+                results.push(SuiteResult::failed(bench.name.clone(), &e));
+            }
+        }
+    }
+
+    let out = mem::take(&mut args.output);
+    let mut output =
+        Output::new(out, args.color_mode, args.format, args.verbose);
+    output.print_suite(&results)?;
+
+    let failed = results.iter().filter(|r| r.status != Status::Ok).count();
+    if failed == 0 {
+        Ok(())
+    } else {
+        Err(Error::SuiteFailed(failed, results.len()))
+    }
+}
+
+/// Measure benchmark of suite. Unless `human` is set, the results are not
+/// printed. All the benchmarks run as children of this process, so their
+/// memory must be taken only from the measured process and not from all the
+/// children (see [`Measurement::measure`]).
+fn measure_benchmark(
+    args: &mut Args,
+    bench: &Benchmark,
+    human: bool,
+) -> Result<Vec<(String, ComMeasure)>> {
+    let mut bargs = args.benchmark(bench)?;
+    bargs.output = if human {
+        args.output.try_clone()?
+    } else {
+        OutputType::Null
+    };
+    measure(bargs)
+}
+
+/// Create the dashboard if it is enabled.
+fn dashboard(args: &Args, total: Option<usize>) -> Result<Option<Dashboard>> {
+    if args.tui {
+        Dashboard::new(total, args.color_mode.stderr()).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Create the live progress line for measurement with the given maximum
+/// number of runs and time limit.
+fn progress(
+    args: &Args,
+    total: Option<usize>,
+    max_time: Option<Duration>,
+) -> Progress {
+    let enabled = !args.no_progress && !args.tui && io::stderr().is_terminal();
+    Progress::new(enabled, args.color_mode.stderr(), total, max_time)
+}
+
+/// Checks whether there were enough runs of the program. `runs` is the
+/// number of started runs, including those that couldn't be measured.
+fn enough_runs(
+    runs: usize,
+    stats: &ComMeasure,
+    args: &Args,
+    elapsed: Duration,
+) -> bool {
+    if !args.is_adaptive() {
+        return runs >= args.repeat;
+    }
+
+    if runs < args.min_runs.unwrap_or(MIN_ADAPTIVE_RUNS) {
+        return false;
+    }
+
+    max_runs(args).is_some_and(|m| runs >= m)
+        || args.target_ci.is_some_and(|t| stats.time_rel_ci() <= t)
+        || args.max_time.is_some_and(|t| elapsed >= t)
 }
 
-fn measure_multiple(args: Args) -> Result<()> {
+fn measure_opts(args: &Args, overhead: Duration) -> MeasureOpts {
+    MeasureOpts {
+        overhead,
+        expect_exit: args.expect_exit,
+        confidence: args.confidence.unwrap_or(DEFAULT_CONFIDENCE),
+        seed: args.seed.unwrap_or_default(),
+    }
+}
+
+/// Significance level used when comparing results.
+fn significance(args: &Args) -> f64 {
+    let alpha = 1. - args.confidence.unwrap_or(DEFAULT_CONFIDENCE);
+    (alpha * 1e6).round() / 1e6
+}
+
+fn measure_multiple(mut args: Args) -> Result<Vec<(String, ComMeasure)>> {
     let program = args.program.as_deref().unwrap_or_default();
-    let cmd = prepare_cmd(program, &args);
-    let mut output = Output::new(args.output, args.color_mode);
+    let mut sampler = Some(CpuSampler::new());
+    let overhead = shell_overhead(&args)?;
+    warmup(program, &args.args, &args)?;
+    let out = mem::take(&mut args.output);
+    let mut output =
+        Output::new(out, args.color_mode, args.format, args.verbose);
+
+    let mut md = Metadata::collect();
+    check_noise(&args, &md, &mut sampler)?;
+    let mut monitor = LoadMonitor::new(&md);
+    let mut stats =
+        ComMeasure::new(measure_opts(&args, overhead), output_check(&args)?);
+    let mut dash = dashboard(&args, max_runs(&args))?;
+    let mut progress = progress(&args, max_runs(&args), args.max_time);
+    let mut stopped = None;
+    let mut failures = 0;
+    let start = Instant::now();
+    let mut i = 0;
+    while !enough_runs(i, &stats, &args, start.elapsed())
+        && !dash.as_ref().is_some_and(|d| d.quit_requested())
+    {
+        i += 1;
+        let res = measure_run(
+            &mut stats,
+            program,
+            &args.args,
+            &args,
+            i,
+            dash.as_mut(),
+        );
+        let failed = !matches!(res, Ok(true));
+        if res.is_err() {
+            progress.clear();
+        }
+        _ = output.print_res_with(i, res.map(|_| ()));
+        if failed && args.stop_on_failure {
+            stopped = Some(Error::StoppedOnFailure(i));
+            break;
+        }
+        failures = if failed { failures + 1 } else { 0 };
+        if args.is_adaptive() && failures >= MAX_FAILURES_IN_ROW {
+            stopped = Some(Error::TooManyFailures(failures));
+            break;
+        }
+        monitor.after_run(i);
+        progress.update(i, &stats);
+    }
 
-    let mut stats = ComMeasure::new(cmd);
-    for i in 0..args.repeat {
-        _ = output.print_res_with(i + 1, stats.measure());
+    progress.clear();
+    drop(dash);
+    md.finish();
+    print_warnings(&args, &monitor.warnings());
+    if let Some(s) = sampler {
+        print_warnings(&args, &s.warnings());
+    }
+    let comparison = match &args.compare {
+        Some(path) => Some(Comparison::new(
+            format!("current vs `{path}`"),
+            &Samples::load(path)?,
+            stats.samples(),
+            significance(&args),
+        )),
+        None => None,
+    };
+    output.print_com_measure(&stats, comparison.as_ref(), &md)?;
+    if args.histogram {
+        output.print_histograms(&[("", &stats)])?;
+    }
+    if let Some(path) = &args.plot {
+        file_write(path, &plot::repeated(&stats))?;
+    }
+
+    if let Some(path) = &args.save_samples {
+        Samples::save(stats.runs(), path)?;
+    }
+    if let Some(path) = &args.html {
+        let mut report = Report::new(&cmd_title(program, &args.args), &md);
+        report.com_measure(&stats);
+        report.comparisons(comparison.as_slice());
+        report.save(path)?;
+    }
+    if let Some(name) = &args.record {
+        let command = cmd_title(program, &args.args);
+        History::append(&Session::new(
+            record_name(name, &command),
+            command,
+            md,
+            stats.runs().to_vec(),
+        ))?;
     }
 
-    output.print_com_measure(&stats)
+    match stopped {
+        Some(e) => Err(e),
+        None => Ok(vec![(String::new(), stats)]),
+    }
+}
+
+/// Single variant of the program in parameter sweep.
+struct Variant {
+    value: String,
+    program: String,
+    args: Vec<String>,
+    stats: ComMeasure,
+    /// Number of started runs.
+    runs: usize,
+    /// Number of the last runs that failed.
+    failures: usize,
+    /// Time spent by the runs of the variant.
+    elapsed: Duration,
+}
+
+fn measure_sweep(mut args: Args) -> Result<Vec<(String, ComMeasure)>> {
+    let Some(param) = args.parameter.take() else {
+        return measure_multiple(args);
+    };
+    let program = args.program.as_deref().unwrap_or_default();
+    let mut sampler = Some(CpuSampler::new());
+    let overhead = shell_overhead(&args)?;
+    let out = mem::take(&mut args.output);
+    let mut output =
+        Output::new(out, args.color_mode, args.format, args.verbose);
+
+    let mut md = Metadata::collect();
+    check_noise(&args, &md, &mut sampler)?;
+    let mut monitor = LoadMonitor::new(&md);
+    let mut variants = vec![];
+    for value in param.values() {
+        let prog = param.substitute(program, &value);
+        let prog_args: Vec<_> = args
+            .args
+            .iter()
+            .map(|a| param.substitute(a, &value))
+            .collect();
+        let stats = ComMeasure::new(
+            measure_opts(&args, overhead),
+            output_check(&args)?,
+        );
+        variants.push(Variant {
+            value,
+            program: prog,
+            args: prog_args,
+            stats,
+            runs: 0,
+            failures: 0,
+            elapsed: Duration::ZERO,
+        });
+    }
+    for v in &variants {
+        warmup(&v.program, &v.args, &args)?;
+    }
+
+    let seed = args.seed.unwrap_or_else(Rng::time_seed);
+    if args.order == RunOrder::Random {
+        md.seed = Some(seed);
+    }
+
+    // Fixed number of runs is planned ahead. Adaptive runs are planned in
+    // rounds of the variants that need more runs.
+    let adaptive = args.is_adaptive();
+    let mut queue: VecDeque<_> = if adaptive {
+        VecDeque::new()
+    } else {
+        let repeat = args.repeat.max(1);
+        let schedule = args.order.schedule(variants.len(), repeat, seed);
+        schedule.into_iter().map(|(v, _)| v).collect()
+    };
+    let mut rng = Rng::new(seed);
+
+    let total = max_runs(&args).map(|m| m.max(1) * variants.len());
+    let max_time = args.max_time.map(|t| t * variants.len() as u32);
+    let mut dash = dashboard(&args, total)?;
+    let mut progress = progress(&args, total, max_time);
+    let mut stopped = None;
+    let mut run = 0;
+    loop {
+        if adaptive && queue.is_empty() {
+            let pending = (0..variants.len())
+                .filter(|&v| {
+                    let var = &variants[v];
+                    !enough_runs(var.runs, &var.stats, &args, var.elapsed)
+                })
+                .collect();
+            queue.extend(args.order.round(pending, &mut rng));
+        }
+        let Some(v) = queue.pop_front() else {
+            break;
+        };
+        if dash.as_ref().is_some_and(|d| d.quit_requested()) {
+            break;
+        }
+
+        run += 1;
+        let var = &mut variants[v];
+        var.runs += 1;
+        let start = Instant::now();
+        let r = measure_run(
+            &mut var.stats,
+            &var.program,
+            &var.args,
+            &args,
+            run,
+            dash.as_mut(),
+        );
+        var.elapsed += start.elapsed();
+        let failed = !matches!(r, Ok(true));
+        if r.is_err() {
+            progress.clear();
+        }
+        let label = format!("{}={} run {}", param.name(), var.value, var.runs);
+        _ = output.print_res_with(label, r.map(|_| ()));
+        if failed && args.stop_on_failure {
+            stopped = Some(Error::StoppedOnFailure(run));
+            break;
+        }
+        var.failures = if failed { var.failures + 1 } else { 0 };
+        if adaptive && var.failures >= MAX_FAILURES_IN_ROW {
+            stopped = Some(Error::TooManyFailures(var.failures));
+            break;
+        }
+        monitor.after_run(run);
+        progress.update(run, &var.stats);
+    }
+    progress.clear();
+    drop(dash);
+    md.finish();
+    print_warnings(&args, &monitor.warnings());
+    if let Some(s) = sampler {
+        print_warnings(&args, &s.warnings());
+    }
+
+    let comparisons: Vec<_> = variants
+        .iter()
+        .skip(1)
+        .map(|v| {
+            Comparison::new(
+                format!(
+                    "{0}={1} vs {0}={2}",
+                    param.name(),
+                    v.value,
+                    variants[0].value
+                ),
+                variants[0].stats.samples(),
+                v.stats.samples(),
+                significance(&args),
+            )
+        })
+        .collect();
+    let res: Vec<_> =
+        variants.into_iter().map(|v| (v.value, v.stats)).collect();
+    output.print_sweep(&param, &res, &comparisons, &md)?;
+    if args.histogram {
+        let hist: Vec<_> = res
+            .iter()
+            .map(|(v, cm)| (format!("{}={v}", param.name()), cm))
+            .collect();
+        let hist: Vec<_> =
+            hist.iter().map(|(n, cm)| (n.as_str(), *cm)).collect();
+        output.print_histograms(&hist)?;
+    }
+    if let Some(path) = &args.plot {
+        file_write(path, &plot::sweep(&param, &res))?;
+    }
+    if let Some(path) = &args.html {
+        let mut report = Report::new(&cmd_title(program, &args.args), &md);
+        report.sweep(&param, &res);
+        report.comparisons(&comparisons);
+        report.save(path)?;
+    }
+    if let Some(name) = &args.record {
+        let command = cmd_title(program, &args.args);
+        let name = record_name(name, &command);
+        for (v, cm) in &res {
+            History::append(&Session::new(
+                format!("{name} {}={v}", param.name()),
+                command.clone(),
+                md.clone(),
+                cm.runs().to_vec(),
+            ))?;
+        }
+    }
+    match stopped {
+        Some(e) => Err(e),
+        None => Ok(res
+            .into_iter()
+            .map(|(v, cm)| (format!("{}={v}", param.name()), cm))
+            .collect()),
+    }
 }
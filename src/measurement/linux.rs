@@ -1,43 +1,142 @@
 use std::{
-    mem,
-    process::Command,
+    io, mem,
+    os::unix::process::ExitStatusExt,
+    process::{Command, ExitStatus},
+    sync::{
+        Mutex, PoisonError,
+        mpsc::{self, RecvTimeoutError},
+    },
+    thread,
     time::{Duration, Instant},
 };
 
-use libc::{RUSAGE_CHILDREN, getrusage, timeval};
+use libc::{
+    P_PID, SIGKILL, WEXITED, WNOWAIT, kill, rusage, siginfo_t, timeval, wait4,
+    waitid,
+};
 
-use crate::err::{Error, Result, child_wait, cmd_spawn};
+use crate::err::{Error, Result, cmd_spawn};
 
 use super::Measurement;
 
 pub fn measure_one(cmd: &mut Command) -> Result<Measurement> {
-    let mut proc = cmd_spawn(cmd)?;
+    let proc = cmd_spawn(cmd)?;
     let start = Instant::now();
-    let code = child_wait(&mut proc, cmd)?.code();
+    let (status, usage) =
+        wait_usage(proc.id()).map_err(|e| wait_err(cmd, e))?;
     let end = Instant::now();
     let time = end - start;
 
-    let peak_memory = get_stats().map(|a| a.0);
+    Ok(measurement(time, status, &usage))
+}
+
+pub fn measure_watched(
+    cmd: &mut Command,
+    interval: Duration,
+    watch: &mut dyn FnMut(u32) -> bool,
+) -> Result<Measurement> {
+    let proc = cmd_spawn(cmd)?;
+    let start = Instant::now();
+    let pid = proc.id();
+
+    // Set when the child is about to be reaped. After that, its pid may be
+    // reused by other process, so it may be killed only while holding the
+    // lock and only if the flag is not set.
+    let reaped = Mutex::new(false);
 
-    Ok(Measurement {
+    let ((status, usage), end) = thread::scope(|s| {
+        let (send, recv) = mpsc::channel();
+        let reaped = &reaped;
+        s.spawn(move || {
+            let res = wait_exit(pid).and_then(|_| {
+                let end = Instant::now();
+                *reaped.lock().unwrap_or_else(PoisonError::into_inner) = true;
+                wait_usage(pid).map(|r| (r, end))
+            });
+            _ = send.send(res);
+        });
+        loop {
+            match recv.recv_timeout(interval) {
+                Ok(r) => return r,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(io::Error::other(
+                        "The waiting thread exited without result.",
+                    ));
+                }
+            }
+            if !watch(pid) {
+                let reaped =
+                    reaped.lock().unwrap_or_else(PoisonError::into_inner);
+                if !*reaped {
+                    unsafe { kill(pid as i32, SIGKILL) };
+                }
+            }
+        }
+    })
+    .map_err(|e| wait_err(cmd, e))?;
+    let time = end - start;
+
+    Ok(measurement(time, status, &usage))
+}
+
+fn measurement(
+    time: Duration,
+    status: ExitStatus,
+    usage: &rusage,
+) -> Measurement {
+    let code = status.code();
+    Measurement {
         time,
-        memory: peak_memory,
+        memory: Ok(get_stats(usage).0),
         exit_code: code,
+        success: code == Some(0),
+        output_matches: None,
+    }
+}
+
+/// Wait for the child with the given pid to exit without reaping it.
+fn wait_exit(pid: u32) -> io::Result<()> {
+    let mut info: siginfo_t = unsafe { mem::zeroed() };
+    retry_interrupted(|| unsafe {
+        waitid(P_PID, pid, &mut info, WEXITED | WNOWAIT)
     })
 }
 
-fn get_stats() -> Result<(usize, Duration)> {
-    let mut stats = unsafe { mem::zeroed() };
+/// Wait for the child with the given pid to exit and get its resource usage.
+/// Unlike `getrusage(RUSAGE_CHILDREN)`, the usage contains only this child
+/// and its descendants and not all the children that were waited for.
+fn wait_usage(pid: u32) -> io::Result<(ExitStatus, rusage)> {
+    let mut status = 0;
+    let mut usage = unsafe { mem::zeroed() };
+    retry_interrupted(|| unsafe {
+        wait4(pid as i32, &mut status, 0, &mut usage)
+    })?;
+    Ok((ExitStatus::from_raw(status), usage))
+}
 
-    let res = unsafe { getrusage(RUSAGE_CHILDREN, &mut stats) };
-    if res == -1 {
-        return Err(Error::Io(std::io::Error::last_os_error()));
+/// Call the system function until it is not interrupted by signal.
+fn retry_interrupted(mut f: impl FnMut() -> i32) -> io::Result<()> {
+    loop {
+        if f() != -1 {
+            return Ok(());
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
     }
+}
+
+fn wait_err(cmd: &Command, e: io::Error) -> Error {
+    Error::FailedToWait(cmd.get_program().to_string_lossy().into_owned(), e)
+}
 
-    let mem = stats.ru_maxrss as usize;
-    let time = get_duration(stats.ru_stime) + get_duration(stats.ru_stime);
+fn get_stats(usage: &rusage) -> (usize, Duration) {
+    let mem = usage.ru_maxrss as usize;
+    let time = get_duration(usage.ru_stime) + get_duration(usage.ru_stime);
 
-    Ok((mem * 1024, time))
+    (mem * 1024, time)
 }
 
 fn get_duration(t: timeval) -> Duration {
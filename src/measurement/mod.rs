@@ -7,7 +7,7 @@ use std::{
 
 use termal::writemcln;
 
-use crate::{err::Result, get_dur_string, get_mem_string};
+use crate::{err::Result, get_dur_string, get_mem_string, json::JsonObject};
 
 #[cfg(target_os = "windows")]
 mod windows;
@@ -19,6 +19,10 @@ pub struct Measurement {
     pub time: Duration,
     pub memory: Result<usize>,
     pub exit_code: Option<i32>,
+    /// Whether the exit code is the expected exit code.
+    pub success: bool,
+    /// Result of output check if output is checked.
+    pub output_matches: Option<bool>,
 }
 
 impl Measurement {
@@ -33,6 +37,52 @@ impl Measurement {
             linux::measure_one(cmd)
         }
     }
+
+    /// Measure the command and call `watch` with id of the running process
+    /// every `interval` until it exits. If `watch` returns `false`, the
+    /// process is killed. On platforms where the process cannot be watched,
+    /// `watch` is never called.
+    pub fn measure_watched(
+        cmd: &mut Command,
+        interval: Duration,
+        watch: &mut dyn FnMut(u32) -> bool,
+    ) -> Result<Self> {
+        #[cfg(target_os = "windows")]
+        {
+            _ = (interval, watch);
+            windows::measure_one(cmd)
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            linux::measure_watched(cmd, interval, watch)
+        }
+    }
+
+    pub fn to_json(&self) -> JsonObject {
+        JsonObject::new()
+            .field("time_ns", self.time)
+            .field("memory", self.memory.as_ref().ok())
+            .field("exit_code", self.exit_code)
+            .field("output_matches", self.output_matches)
+    }
+
+    pub const CSV_HEADER: &str = "time_ns,memory,exit_code,output_matches";
+
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{}",
+            self.time.as_nanos(),
+            self.memory
+                .as_ref()
+                .map(|m| m.to_string())
+                .unwrap_or_default(),
+            self.exit_code.map(|c| c.to_string()).unwrap_or_default(),
+            self.output_matches
+                .map(|m| m.to_string())
+                .unwrap_or_default(),
+        )
+    }
 }
 
 impl Display for Measurement {
@@ -83,13 +133,29 @@ impl Display for Measurement {
 
         match self.exit_code {
             Some(e) => {
-                if e == 0 {
-                    writemcln!(f, color, "{'dg}Exit code: {'g bold}{}{'_}", e)
+                if self.success {
+                    writemcln!(f, color, "{'dg}Exit code: {'g bold}{}{'_}", e)?
                 } else {
-                    writemcln!(f, color, "{'dr}Exit code: {'r bold}{}{'_}", e)
+                    writemcln!(f, color, "{'dr}Exit code: {'r bold}{}{'_}", e)?
+                }
+            }
+            None => writemcln!(f, color, "{'dr}No exit code{'_}")?,
+        }
+
+        match self.output_matches {
+            Some(true) => {
+                if w > 0 {
+                    write!(f, "{:>w$}", ' ')?;
+                }
+                writemcln!(f, color, "{'dg}Output: {'g bold}matches{'_}")
+            }
+            Some(false) => {
+                if w > 0 {
+                    write!(f, "{:>w$}", ' ')?;
                 }
+                writemcln!(f, color, "{'dr}Output: {'r bold}differs{'_}")
             }
-            None => writemcln!(f, color, "{'dr}No exit code{'_}"),
+            None => Ok(()),
         }
     }
 }
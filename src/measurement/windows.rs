@@ -28,6 +28,8 @@ pub fn measure_one(cmd: &mut Command) -> Result<Measurement> {
         time: time.unwrap_or(fallback_time),
         memory: peak_memory,
         exit_code: res.code(),
+        success: res.success(),
+        output_matches: None,
     })
 }
 
@@ -0,0 +1,242 @@
+use std::{
+    env,
+    fmt::Display,
+    fs,
+    io::{self, IsTerminal},
+    path::Path,
+    thread,
+};
+
+use termal::writemcln;
+
+use crate::{get_mem_string, json::JsonObject};
+
+/// Information about the host and environment of the measurement. Values
+/// that are not available on the current system are [`None`].
+#[derive(Debug, Clone, Default)]
+pub struct Metadata {
+    pub hostname: Option<String>,
+    pub os: String,
+    pub arch: String,
+    pub kernel: Option<String>,
+    pub cpu_model: Option<String>,
+    pub cores: usize,
+    /// CPU frequency scaling governor.
+    pub governor: Option<String>,
+    pub total_memory: Option<usize>,
+    /// Load average (1, 5 and 15 minutes) before the measurement.
+    pub load_start: Option<[f64; 3]>,
+    /// Load average (1, 5 and 15 minutes) after the measurement.
+    pub load_end: Option<[f64; 3]>,
+    pub version: String,
+    /// Commit of the git repository in the current directory.
+    pub git_commit: Option<String>,
+    /// Seed of the random order of runs.
+    pub seed: Option<u64>,
+}
+
+impl Metadata {
+    /// Collect the metadata. Should be called before the measurement.
+    pub fn collect() -> Self {
+        Self {
+            hostname: read_trimmed("/proc/sys/kernel/hostname")
+                .or_else(|| env::var("COMPUTERNAME").ok()),
+            os: env::consts::OS.to_string(),
+            arch: env::consts::ARCH.to_string(),
+            kernel: read_trimmed("/proc/sys/kernel/osrelease"),
+            cpu_model: cpu_model(),
+            cores: thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+            governor: read_trimmed(
+                "/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor",
+            ),
+            total_memory: total_memory(),
+            load_start: load_avg(),
+            load_end: None,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: git_commit(),
+            seed: None,
+        }
+    }
+
+    /// Record the state after the measurement.
+    pub fn finish(&mut self) {
+        self.load_end = load_avg();
+    }
+
+    pub fn to_json(&self) -> JsonObject {
+        JsonObject::new()
+            .field("hostname", &self.hostname)
+            .field("os", &self.os)
+            .field("arch", &self.arch)
+            .field("kernel", &self.kernel)
+            .field("cpu_model", &self.cpu_model)
+            .field("cores", self.cores)
+            .field("governor", &self.governor)
+            .field("total_memory", self.total_memory)
+            .field("load_start", self.load_start.map(|l| l.to_vec()))
+            .field("load_end", self.load_end.map(|l| l.to_vec()))
+            .field("mproc_version", &self.version)
+            .field("git_commit", &self.git_commit)
+            .field("seed", self.seed)
+    }
+
+    /// Create metadata from the values returned by [`Self::fields`] with
+    /// `human` set to `false`. Unknown keys and invalid values are ignored.
+    pub fn from_fields<'a>(
+        fields: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Self {
+        let load = |v: &str| {
+            let mut vals = v.split_whitespace().map(|v| v.parse().ok());
+            Some([vals.next()??, vals.next()??, vals.next()??])
+        };
+        let mut res = Self::default();
+        for (k, v) in fields {
+            let s = Some(v.to_string());
+            match k {
+                "hostname" => res.hostname = s,
+                "os" => res.os = v.to_string(),
+                "arch" => res.arch = v.to_string(),
+                "kernel" => res.kernel = s,
+                "cpu_model" => res.cpu_model = s,
+                "cores" => res.cores = v.parse().unwrap_or_default(),
+                "governor" => res.governor = s,
+                "total_memory" => res.total_memory = v.parse().ok(),
+                "load_start" => res.load_start = load(v),
+                "load_end" => res.load_end = load(v),
+                "mproc_version" => res.version = v.to_string(),
+                "git_commit" => res.git_commit = s,
+                "seed" => res.seed = v.parse().ok(),
+                _ => {}
+            }
+        }
+        res
+    }
+
+    /// Get the available values with their keys. If `human` is `true`,
+    /// memory is formatted with units, otherwise it is in bytes.
+    pub fn fields(&self, human: bool) -> Vec<(&'static str, String)> {
+        let mem = |m: usize| {
+            if human {
+                get_mem_string(m)
+            } else {
+                m.to_string()
+            }
+        };
+        let load = |l: [f64; 3]| format!("{} {} {}", l[0], l[1], l[2]);
+        [
+            ("hostname", self.hostname.clone()),
+            ("os", Some(self.os.clone())),
+            ("arch", Some(self.arch.clone())),
+            ("kernel", self.kernel.clone()),
+            ("cpu_model", self.cpu_model.clone()),
+            ("cores", Some(self.cores.to_string())),
+            ("governor", self.governor.clone()),
+            ("total_memory", self.total_memory.map(mem)),
+            ("load_start", self.load_start.map(load)),
+            ("load_end", self.load_end.map(load)),
+            ("mproc_version", Some(self.version.clone())),
+            ("git_commit", self.git_commit.clone()),
+            ("seed", self.seed.map(|s| s.to_string())),
+        ]
+        .into_iter()
+        .filter_map(|(k, v)| v.map(|v| (k, v)))
+        .collect()
+    }
+}
+
+impl Display for Metadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let color = f
+            .precision()
+            .map(|p| p != 0)
+            .unwrap_or_else(|| io::stderr().is_terminal());
+
+        if f.sign_minus() {
+            writemcln!(
+                f,
+                color,
+                "
+{'gr}============<< {'y}mproc environment {'gr}>>============{'_}"
+            )?;
+        }
+
+        for (k, v) in self.fields(true) {
+            writemcln!(f, color, "{'dm}{k:<13}: {'_}{v}")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn read_trimmed(path: impl AsRef<Path>) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn cpu_model() -> Option<String> {
+    let info = fs::read_to_string("/proc/cpuinfo").ok()?;
+    info.lines()
+        .find(|l| l.starts_with("model name"))
+        .and_then(|l| l.split_once(':'))
+        .map(|(_, v)| v.trim().to_string())
+}
+
+fn total_memory() -> Option<usize> {
+    let info = fs::read_to_string("/proc/meminfo").ok()?;
+    let kib: usize = info
+        .lines()
+        .find_map(|l| l.strip_prefix("MemTotal:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+fn load_avg() -> Option<[f64; 3]> {
+    let load = fs::read_to_string("/proc/loadavg").ok()?;
+    let mut vals = load.split_whitespace().map(|v| v.parse().ok());
+    Some([vals.next()??, vals.next()??, vals.next()??])
+}
+
+/// Get the commit of the git repository that contains the current
+/// directory. The repository is read directly so that no child process is
+/// started.
+fn git_commit() -> Option<String> {
+    let cwd = env::current_dir().ok()?;
+    let git = cwd
+        .ancestors()
+        .map(|d| d.join(".git"))
+        .find(|g| g.exists())?;
+    // In worktrees and submodules, `.git` is file with path to the git
+    // directory.
+    let git = if git.is_file() {
+        let dir = read_trimmed(&git)?;
+        git.parent()?.join(dir.strip_prefix("gitdir:")?.trim())
+    } else {
+        git
+    };
+    // Branches are shared by all the worktrees in the common directory.
+    let common = read_trimmed(git.join("commondir"))
+        .map_or_else(|| git.clone(), |c| git.join(c));
+
+    let head = read_trimmed(git.join("HEAD"))?;
+    let Some(name) = head.strip_prefix("ref:").map(str::trim) else {
+        return Some(head);
+    };
+    read_trimmed(git.join(name))
+        .or_else(|| read_trimmed(common.join(name)))
+        .or_else(|| {
+            let packed =
+                fs::read_to_string(common.join("packed-refs")).ok()?;
+            packed.lines().find_map(|l| {
+                let (commit, r) = l.split_once(' ')?;
+                (r == name).then(|| commit.to_string())
+            })
+        })
+}
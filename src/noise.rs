@@ -0,0 +1,244 @@
+use std::{
+    fs, process, thread,
+    time::{Duration, Instant},
+};
+
+use crate::{get_mem_string, metadata::Metadata, proc_sample::ticks_per_sec};
+
+/// Processes that use more CPU than this (`1` is one whole CPU) are reported.
+const HEAVY_PROCESS_CPU: f64 = 0.5;
+/// Minimal time for which the CPU usage of other processes is measured.
+const PROCESS_SAMPLE_TIME: Duration = Duration::from_millis(200);
+/// Fraction of swap that may be used without warning.
+const MAX_SWAP_USED: f64 = 0.1;
+/// Load average per CPU that may be present without warning.
+const MAX_LOAD_PER_CPU: f64 = 0.5;
+/// How often is the load checked during the measurement.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Check the system for conditions that make timings unreliable. Returns
+/// description of each of the problems. Processes with high CPU usage are
+/// checked separately by [`CpuSampler`].
+pub fn check(md: &Metadata) -> Vec<String> {
+    let mut res = vec![];
+
+    if let Some([load, ..]) = md.load_start
+        && load > md.cores as f64 * MAX_LOAD_PER_CPU
+    {
+        res.push(format!("Load average is {load:.2} with {} CPUs.", md.cores));
+    }
+
+    if let Some(g) = &md.governor
+        && g != "performance"
+    {
+        res.push(format!(
+            "CPU frequency scaling governor is `{g}`. Use `performance` for \
+            stable timings."
+        ));
+    }
+
+    if turbo_enabled() {
+        res.push("Turbo boost is enabled.".to_string());
+    }
+
+    if let Some((used, total)) = swap()
+        && used as f64 > total as f64 * MAX_SWAP_USED
+    {
+        res.push(format!(
+            "{} of {} swap is used.",
+            get_mem_string(used),
+            get_mem_string(total)
+        ));
+    }
+
+    res
+}
+
+/// Measures CPU usage of other processes since its creation. Processes
+/// started later (e.g. the measured program) are ignored, so the usage may be
+/// sampled while the program runs.
+#[derive(Debug)]
+pub struct CpuSampler {
+    start: Instant,
+    /// CPU times of the processes at the start.
+    before: Vec<(u32, String, u64)>,
+}
+
+/// Periodically checks the load of the system during the measurement and
+/// remembers the runs that happened during load spike.
+#[derive(Debug)]
+pub struct LoadMonitor {
+    threshold: f64,
+    last_check: Instant,
+    first_unchecked: usize,
+    /// First and last run during the spike with the maximum load.
+    spikes: Vec<(usize, usize, f64)>,
+}
+
+impl LoadMonitor {
+    /// Create monitor. The load is compared to the load before the
+    /// measurement. The measured program itself may add load of one.
+    pub fn new(md: &Metadata) -> Self {
+        let base = md.load_start.map_or(0., |l| l[0]);
+        Self {
+            threshold: base + md.cores as f64 * MAX_LOAD_PER_CPU + 1.,
+            last_check: Instant::now(),
+            first_unchecked: 1,
+            spikes: vec![],
+        }
+    }
+
+    /// Check the load after the given run if it wasn't checked recently.
+    pub fn after_run(&mut self, run: usize) {
+        if self.last_check.elapsed() < RECHECK_INTERVAL {
+            return;
+        }
+        self.last_check = Instant::now();
+        let first = self.first_unchecked;
+        self.first_unchecked = run + 1;
+
+        let Some(load) = load_1() else {
+            return;
+        };
+        if load <= self.threshold {
+            return;
+        }
+
+        match self.spikes.last_mut() {
+            Some((_, last, max)) if *last + 1 == first => {
+                *last = run;
+                *max = max.max(load);
+            }
+            _ => self.spikes.push((first, run, load)),
+        }
+    }
+
+    /// Get warnings about the runs that happened during load spike.
+    pub fn warnings(&self) -> Vec<String> {
+        self.spikes
+            .iter()
+            .map(|(first, last, load)| {
+                let runs = if first == last {
+                    format!("run {first}")
+                } else {
+                    format!("runs {first}-{last}")
+                };
+                format!(
+                    "Load spike (load average {load:.2}) during {runs}. The \
+                    timings may be unreliable."
+                )
+            })
+            .collect()
+    }
+}
+
+impl CpuSampler {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            before: cpu_times(),
+        }
+    }
+
+    /// Checks whether the usage was sampled long enough to be reliable.
+    pub fn ready(&self) -> bool {
+        self.start.elapsed() >= PROCESS_SAMPLE_TIME
+    }
+
+    /// Wait until the usage is sampled long enough.
+    pub fn wait(&self) {
+        thread::sleep(
+            PROCESS_SAMPLE_TIME.saturating_sub(self.start.elapsed()),
+        );
+    }
+
+    /// Get warnings about the processes that used a lot of CPU since the
+    /// creation of the sampler.
+    pub fn warnings(&self) -> Vec<String> {
+        if self.before.is_empty() {
+            return vec![];
+        }
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let ticks = ticks_per_sec();
+
+        let mut heavy: Vec<_> = cpu_times()
+            .into_iter()
+            .filter_map(|(pid, name, t)| {
+                let (_, _, prev) = self.before.iter().find(|p| p.0 == pid)?;
+                let usage = t.saturating_sub(*prev) as f64 / ticks / elapsed;
+                (usage > HEAVY_PROCESS_CPU).then_some((pid, name, usage))
+            })
+            .collect();
+        heavy.sort_by(|a, b| b.2.total_cmp(&a.2));
+        heavy
+            .into_iter()
+            .map(|(pid, name, usage)| {
+                format!(
+                    "Process `{name}` ({pid}) uses {:.0}% CPU.",
+                    usage * 100.
+                )
+            })
+            .collect()
+    }
+}
+
+fn load_1() -> Option<f64> {
+    fs::read_to_string("/proc/loadavg")
+        .ok()?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+fn turbo_enabled() -> bool {
+    let read = |p: &str| fs::read_to_string(p).ok().map(|s| s.trim() == "1");
+    // intel_pstate has inverted logic
+    read("/sys/devices/system/cpu/intel_pstate/no_turbo")
+        .map(|no_turbo| !no_turbo)
+        .or_else(|| read("/sys/devices/system/cpu/cpufreq/boost"))
+        .unwrap_or(false)
+}
+
+/// Get the used and total swap in bytes.
+fn swap() -> Option<(usize, usize)> {
+    let info = fs::read_to_string("/proc/meminfo").ok()?;
+    let field = |name: &str| {
+        info.lines()
+            .find_map(|l| l.strip_prefix(name))?
+            .trim()
+            .strip_suffix("kB")?
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .map(|v| v * 1024)
+    };
+    let total = field("SwapTotal:")?;
+    let free = field("SwapFree:")?;
+    (total != 0).then_some((total - free, total))
+}
+
+/// Get CPU times in clock ticks of all other processes with their pid and
+/// name.
+fn cpu_times() -> Vec<(u32, String, u64)> {
+    let Ok(dir) = fs::read_dir("/proc") else {
+        return vec![];
+    };
+    let me = process::id();
+    dir.flatten()
+        .filter_map(|e| {
+            let pid: u32 = e.file_name().to_str()?.parse().ok()?;
+            if pid == me {
+                return None;
+            }
+            let stat = fs::read_to_string(e.path().join("stat")).ok()?;
+            // The name may contain spaces and parentheses.
+            let (name, fields) = stat.split_once(" (")?.1.rsplit_once(')')?;
+            let fields: Vec<_> = fields.split_whitespace().collect();
+            // utime and stime
+            let utime: u64 = fields.get(11)?.parse().ok()?;
+            let stime: u64 = fields.get(12)?.parse().ok()?;
+            Some((pid, name.to_string(), utime + stime))
+        })
+        .collect()
+}
@@ -0,0 +1,57 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// Checks that the output of the program is the same for all runs.
+#[derive(Debug, Default)]
+pub struct OutputCheck {
+    expected: Option<Vec<u8>>,
+    /// Hashes of stdout and stderr of the first run. Stdout is not compared
+    /// with the first run if the expected output is given.
+    first: Option<(Option<u64>, Option<u64>)>,
+    checked: usize,
+    mismatches: usize,
+}
+
+impl OutputCheck {
+    /// Create new check. If `expected` is given, stdout of each run is
+    /// compared with it.
+    pub fn new(expected: Option<Vec<u8>>) -> Self {
+        Self {
+            expected,
+            ..Default::default()
+        }
+    }
+
+    /// Check output of single run. Returns `true` if the output matches.
+    pub fn check(&mut self, stdout: &[u8], stderr: Option<&[u8]>) -> bool {
+        let hash = |data: &[u8]| {
+            let mut hasher = DefaultHasher::new();
+            data.hash(&mut hasher);
+            hasher.finish()
+        };
+        let out = match &self.expected {
+            Some(_) => None,
+            None => Some(hash(stdout)),
+        };
+        let cur = (out, stderr.map(hash));
+
+        let first = *self.first.get_or_insert(cur);
+        let matches =
+            first == cur && self.expected.as_ref().is_none_or(|e| e == stdout);
+
+        self.checked += 1;
+        if !matches {
+            self.mismatches += 1;
+        }
+        matches
+    }
+
+    /// Number of checked runs.
+    pub fn checked(&self) -> usize {
+        self.checked
+    }
+
+    /// Number of runs with different output.
+    pub fn mismatches(&self) -> usize {
+        self.mismatches
+    }
+}
@@ -0,0 +1,569 @@
+use std::{fmt::Write as _, time::Duration};
+
+use crate::{
+    cli::Parameter, com_measure::ComMeasure, measurement::Measurement, stats,
+};
+
+/// Width of the whole chart.
+const WIDTH: f64 = 800.;
+/// Height of single panel including its margins.
+const PANEL_HEIGHT: f64 = 360.;
+const MARGIN_LEFT: f64 = 90.;
+const MARGIN_RIGHT: f64 = 30.;
+const MARGIN_TOP: f64 = 40.;
+const MARGIN_BOTTOM: f64 = 50.;
+const TIME_COLOR: &str = "#9c3fb8";
+const MEMORY_COLOR: &str = "#1f8fae";
+const AXIS_COLOR: &str = "#444";
+const GRID_COLOR: &str = "#ddd";
+/// Number of points used to draw the violin plot.
+const VIOLIN_POINTS: usize = 60;
+
+/// Get SVG chart of memory of single run over time. `curve` contains samples
+/// of RSS at times since the start of the run.
+pub fn single(curve: &[(Duration, usize)], m: &Measurement) -> String {
+    let mut svg = Svg::new(WIDTH, PANEL_HEIGHT);
+    let peak = m.memory.as_ref().ok().copied();
+    let max_mem = curve
+        .iter()
+        .map(|c| c.1)
+        .chain(peak)
+        .max()
+        .unwrap_or_default();
+
+    let panel = Panel::new(
+        0.,
+        0.,
+        WIDTH,
+        PANEL_HEIGHT,
+        (0., m.time.as_secs_f64().max(f64::EPSILON)),
+        padded_range(&[0., max_mem as f64]),
+    );
+    panel.frame(&mut svg, "Memory over time", &mem_label);
+    panel.x_ticks(&mut svg, &|v| dur_label(v * 1e9));
+
+    if let Some(peak) = peak {
+        let y = panel.py(peak as f64);
+        svg.dashed_line(panel.x, y, panel.x + panel.w, y, MEMORY_COLOR);
+        svg.text(
+            panel.x + panel.w - 4.,
+            y - 4.,
+            "end",
+            &format!("peak {}", mem_label(peak as f64)),
+        );
+    }
+
+    if curve.is_empty() {
+        svg.text(
+            panel.x + panel.w / 2.,
+            panel.y + panel.h / 2.,
+            "middle",
+            "The run was too short to sample its memory.",
+        );
+    } else {
+        let points: Vec<_> = curve
+            .iter()
+            .map(|(t, m)| (panel.px(t.as_secs_f64()), panel.py(*m as f64)))
+            .collect();
+        svg.polyline(&points, MEMORY_COLOR);
+    }
+
+    svg.finish()
+}
+
+/// Get SVG with violin and box plots of times and memory of repeated runs.
+pub fn repeated(cm: &ComMeasure) -> String {
+    let mut svg = Svg::new(WIDTH, PANEL_HEIGHT);
+    let samples = cm.samples();
+    let half = WIDTH / 2.;
+
+    let times = samples.times_ns();
+    let panel =
+        Panel::new(0., 0., half, PANEL_HEIGHT, (0., 1.), padded_range(&times));
+    panel.frame(&mut svg, "Time", &dur_label);
+    violin(&mut svg, &panel, &times, TIME_COLOR);
+
+    let mems = samples.memories_f64();
+    let panel = Panel::new(
+        half,
+        0.,
+        half,
+        PANEL_HEIGHT,
+        (0., 1.),
+        padded_range(&mems),
+    );
+    panel.frame(&mut svg, "Memory", &mem_label);
+    violin(&mut svg, &panel, &mems, MEMORY_COLOR);
+
+    svg.finish()
+}
+
+/// Get SVG with line charts of time and memory for each value of the
+/// parameter.
+pub fn sweep(param: &Parameter, res: &[(String, ComMeasure)]) -> String {
+    let mut svg = Svg::new(WIDTH, PANEL_HEIGHT * 2.);
+
+    let numeric: Option<Vec<_>> = res
+        .iter()
+        .map(|r| Some((r.0.trim().parse::<f64>().ok()?, r)))
+        .collect();
+    let is_numeric = numeric.is_some();
+    // Numeric values are sorted so that the lines don't go back and forth.
+    let (xs, res): (Vec<_>, Vec<_>) = match numeric {
+        Some(mut n) => {
+            n.sort_by(|a, b| a.0.total_cmp(&b.0));
+            n.into_iter().unzip()
+        }
+        None => res.iter().enumerate().map(|(i, r)| (i as f64, r)).unzip(),
+    };
+    let x_range = padded_range(&xs);
+    let x_label = |svg: &mut Svg, panel: &Panel| {
+        if is_numeric {
+            panel.x_ticks(svg, &|v| short_num(v));
+        } else {
+            for (x, (v, _)) in xs.iter().zip(&res) {
+                panel.x_tick(svg, *x, v);
+            }
+        }
+        svg.text(
+            panel.x + panel.w / 2.,
+            panel.y + panel.h + 40.,
+            "middle",
+            param.name(),
+        );
+    };
+
+    let estimates: Vec<_> = res.iter().map(|(_, cm)| cm.estimates()).collect();
+
+    // Time
+    let avg: Vec<_> = res
+        .iter()
+        .map(|(_, cm)| cm.avg_time().as_nanos() as f64)
+        .collect();
+    let low: Vec<_> = estimates
+        .iter()
+        .zip(&avg)
+        .map(|(e, a)| e.time_mean.map_or(*a, |e| e.low))
+        .collect();
+    let high: Vec<_> = estimates
+        .iter()
+        .zip(&avg)
+        .map(|(e, a)| e.time_mean.map_or(*a, |e| e.high))
+        .collect();
+    let panel = Panel::new(
+        0.,
+        0.,
+        WIDTH,
+        PANEL_HEIGHT,
+        x_range,
+        padded_range(&[low.as_slice(), &high].concat()),
+    );
+    panel.frame(&mut svg, "Average time", &dur_label);
+    x_label(&mut svg, &panel);
+    series(&mut svg, &panel, &xs, &avg, Some((&low, &high)), TIME_COLOR);
+
+    // Memory
+    let mems: Vec<_> = res
+        .iter()
+        .map(|(_, cm)| cm.avg_memory().map_or(f64::NAN, |m| m as f64))
+        .collect();
+    let panel = Panel::new(
+        0.,
+        PANEL_HEIGHT,
+        WIDTH,
+        PANEL_HEIGHT,
+        x_range,
+        padded_range(&mems),
+    );
+    panel.frame(&mut svg, "Average memory", &mem_label);
+    x_label(&mut svg, &panel);
+    series(&mut svg, &panel, &xs, &mems, None, MEMORY_COLOR);
+
+    svg.finish()
+}
+
+/// Draw line with points and optional error band.
+fn series(
+    svg: &mut Svg,
+    panel: &Panel,
+    xs: &[f64],
+    ys: &[f64],
+    band: Option<(&[f64], &[f64])>,
+    color: &str,
+) {
+    if let Some((low, high)) = band {
+        let mut pts: Vec<_> = xs
+            .iter()
+            .zip(high)
+            .map(|(x, y)| (panel.px(*x), panel.py(*y)))
+            .collect();
+        pts.extend(
+            xs.iter()
+                .zip(low)
+                .rev()
+                .map(|(x, y)| (panel.px(*x), panel.py(*y))),
+        );
+        svg.polygon(&pts, color, 0.2);
+    }
+
+    let pts: Vec<_> = xs
+        .iter()
+        .zip(ys)
+        .filter(|(_, y)| y.is_finite())
+        .map(|(x, y)| (panel.px(*x), panel.py(*y)))
+        .collect();
+    svg.polyline(&pts, color);
+    for (x, y) in pts {
+        svg.circle(x, y, 3.5, color);
+    }
+}
+
+/// Draw violin plot with box plot inside it to the middle of the panel.
+fn violin(svg: &mut Svg, panel: &Panel, values: &[f64], color: &str) {
+    if values.is_empty() {
+        svg.text(
+            panel.x + panel.w / 2.,
+            panel.y + panel.h / 2.,
+            "middle",
+            "No samples.",
+        );
+        return;
+    }
+
+    let cx = panel.x + panel.w / 2.;
+    let max_half = panel.w * 0.35;
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let q1 = stats::percentile(&sorted, 0.25);
+    let q3 = stats::percentile(&sorted, 0.75);
+    let median = stats::percentile(&sorted, 0.5);
+    let fence = (q3 - q1) * 1.5;
+    let lo = sorted.iter().copied().find(|v| *v >= q1 - fence).unwrap();
+    let hi = sorted.iter().copied().rfind(|v| *v <= q3 + fence).unwrap();
+
+    // Silverman's rule of thumb
+    let sd = stats::std_dev(values);
+    let spread = if q3 > q1 {
+        sd.min((q3 - q1) / 1.34)
+    } else {
+        sd
+    };
+    let bw = 0.9 * spread * (values.len() as f64).powf(-0.2);
+    if bw > 0. {
+        let (start, end) =
+            (sorted[0] - bw * 2., sorted[sorted.len() - 1] + bw * 2.);
+        let grid: Vec<_> = (0..VIOLIN_POINTS)
+            .map(|i| {
+                start + (end - start) * i as f64 / (VIOLIN_POINTS - 1) as f64
+            })
+            .filter(|y| *y >= panel.y_range.0 && *y <= panel.y_range.1)
+            .collect();
+        let density: Vec<_> = grid
+            .iter()
+            .map(|y| {
+                values
+                    .iter()
+                    .map(|v| (-0.5 * ((y - v) / bw).powi(2)).exp())
+                    .sum::<f64>()
+            })
+            .collect();
+        let max = density.iter().copied().fold(0., f64::max);
+        let mut pts: Vec<_> = grid
+            .iter()
+            .zip(&density)
+            .map(|(y, d)| (cx + d / max * max_half, panel.py(*y)))
+            .collect();
+        pts.extend(
+            grid.iter()
+                .zip(&density)
+                .rev()
+                .map(|(y, d)| (cx - d / max * max_half, panel.py(*y))),
+        );
+        svg.polygon(&pts, color, 0.3);
+    }
+
+    let bw = 14.;
+    svg.line(cx, panel.py(lo), cx, panel.py(hi), AXIS_COLOR, 1.5);
+    svg.rect(
+        cx - bw / 2.,
+        panel.py(q3),
+        bw,
+        (panel.py(q1) - panel.py(q3)).max(1.),
+        color,
+    );
+    svg.line(
+        cx - bw / 2.,
+        panel.py(median),
+        cx + bw / 2.,
+        panel.py(median),
+        "#fff",
+        2.,
+    );
+    svg.circle(cx, panel.py(stats::mean(values)), 3., "#fff");
+
+    for v in sorted.iter().filter(|v| **v < lo || **v > hi) {
+        svg.circle(cx, panel.py(*v), 2.5, AXIS_COLOR);
+    }
+}
+
+/// Area of single chart with its axes.
+struct Panel {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+}
+
+impl Panel {
+    /// Create panel from its outer rectangle (including margins).
+    fn new(
+        x: f64,
+        y: f64,
+        w: f64,
+        h: f64,
+        x_range: (f64, f64),
+        y_range: (f64, f64),
+    ) -> Self {
+        Self {
+            x: x + MARGIN_LEFT,
+            y: y + MARGIN_TOP,
+            w: w - MARGIN_LEFT - MARGIN_RIGHT,
+            h: h - MARGIN_TOP - MARGIN_BOTTOM,
+            x_range,
+            y_range,
+        }
+    }
+
+    fn px(&self, v: f64) -> f64 {
+        let (lo, hi) = self.x_range;
+        self.x + (v - lo) / (hi - lo) * self.w
+    }
+
+    fn py(&self, v: f64) -> f64 {
+        let (lo, hi) = self.y_range;
+        self.y + self.h - (v - lo) / (hi - lo) * self.h
+    }
+
+    /// Draw the title, axes and labels of the y axis.
+    fn frame(&self, svg: &mut Svg, title: &str, fmt: &dyn Fn(f64) -> String) {
+        svg.title(self.x + self.w / 2., self.y - 16., title);
+        for t in ticks(self.y_range) {
+            let y = self.py(t);
+            svg.line(self.x, y, self.x + self.w, y, GRID_COLOR, 1.);
+            svg.text(self.x - 6., y + 4., "end", &fmt(t));
+        }
+        let bottom = self.y + self.h;
+        svg.line(self.x, self.y, self.x, bottom, AXIS_COLOR, 1.);
+        svg.line(self.x, bottom, self.x + self.w, bottom, AXIS_COLOR, 1.);
+    }
+
+    /// Draw labels of the x axis.
+    fn x_ticks(&self, svg: &mut Svg, fmt: &dyn Fn(f64) -> String) {
+        for t in ticks(self.x_range) {
+            self.x_tick(svg, t, &fmt(t));
+        }
+    }
+
+    fn x_tick(&self, svg: &mut Svg, v: f64, label: &str) {
+        let x = self.px(v);
+        let bottom = self.y + self.h;
+        svg.line(x, bottom, x, bottom + 4., AXIS_COLOR, 1.);
+        svg.text(x, bottom + 18., "middle", label);
+    }
+}
+
+/// Get range of the values with small padding.
+fn padded_range(values: &[f64]) -> (f64, f64) {
+    let finite = values.iter().copied().filter(|v| v.is_finite());
+    let lo = finite.clone().fold(f64::INFINITY, f64::min);
+    let hi = finite.fold(f64::NEG_INFINITY, f64::max);
+    if !lo.is_finite() {
+        return (0., 1.);
+    }
+    if lo == hi {
+        let pad = if lo == 0. { 1. } else { lo.abs() * 0.1 };
+        return (lo - pad, hi + pad);
+    }
+    let pad = (hi - lo) * 0.05;
+    (lo - pad, hi + pad)
+}
+
+/// Get nicely rounded values of ticks in the range.
+fn ticks((lo, hi): (f64, f64)) -> Vec<f64> {
+    let raw = (hi - lo) / 5.;
+    let mag = 10f64.powf(raw.log10().floor());
+    let step = [1., 2., 5., 10.]
+        .into_iter()
+        .map(|m| m * mag)
+        .find(|s| *s >= raw)
+        .unwrap_or(mag * 10.);
+    let mut res = vec![];
+    let mut t = (lo / step).ceil() * step;
+    while t <= hi {
+        res.push(t);
+        t += step;
+    }
+    res
+}
+
+/// Format number with at most 3 significant digits.
+fn short_num(v: f64) -> String {
+    if v == 0. {
+        return "0".to_string();
+    }
+    let digits = (2 - v.abs().log10().floor() as i32).max(0) as usize;
+    let s = format!("{v:.digits$}");
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        s
+    }
+}
+
+/// Format time in nanoseconds.
+fn dur_label(ns: f64) -> String {
+    const UNITS: &[(f64, &str)] =
+        &[(1e9, "s"), (1e6, "ms"), (1e3, "μs"), (1., "ns")];
+    let (d, u) = UNITS
+        .iter()
+        .find(|(d, _)| ns.abs() >= *d)
+        .unwrap_or(&UNITS[UNITS.len() - 1]);
+    format!("{} {u}", short_num(ns / d))
+}
+
+/// Format memory in bytes.
+fn mem_label(b: f64) -> String {
+    const UNITS: &[(f64, &str)] = &[
+        (1073741824., "GiB"),
+        (1048576., "MiB"),
+        (1024., "KiB"),
+        (1., "B"),
+    ];
+    let (d, u) = UNITS
+        .iter()
+        .find(|(d, _)| b.abs() >= *d)
+        .unwrap_or(&UNITS[UNITS.len() - 1]);
+    format!("{} {u}", short_num(b / d))
+}
+
+/// Simple builder of SVG document.
+struct Svg {
+    width: f64,
+    height: f64,
+    body: String,
+}
+
+impl Svg {
+    fn new(width: f64, height: f64) -> Self {
+        Self {
+            width,
+            height,
+            body: String::new(),
+        }
+    }
+
+    fn line(
+        &mut self,
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        color: &str,
+        width: f64,
+    ) {
+        _ = writeln!(
+            self.body,
+            r#"<line x1="{x1:.1}" y1="{y1:.1}" x2="{x2:.1}" y2="{y2:.1}" stroke="{color}" stroke-width="{width}"/>"#
+        );
+    }
+
+    fn dashed_line(
+        &mut self,
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        color: &str,
+    ) {
+        _ = writeln!(
+            self.body,
+            r#"<line x1="{x1:.1}" y1="{y1:.1}" x2="{x2:.1}" y2="{y2:.1}" stroke="{color}" stroke-dasharray="6 4"/>"#
+        );
+    }
+
+    fn rect(&mut self, x: f64, y: f64, w: f64, h: f64, color: &str) {
+        _ = writeln!(
+            self.body,
+            r#"<rect x="{x:.1}" y="{y:.1}" width="{w:.1}" height="{h:.1}" fill="{color}"/>"#
+        );
+    }
+
+    fn circle(&mut self, x: f64, y: f64, r: f64, color: &str) {
+        _ = writeln!(
+            self.body,
+            r#"<circle cx="{x:.1}" cy="{y:.1}" r="{r}" fill="{color}"/>"#
+        );
+    }
+
+    fn polyline(&mut self, points: &[(f64, f64)], color: &str) {
+        _ = writeln!(
+            self.body,
+            r#"<polyline points="{}" fill="none" stroke="{color}" stroke-width="2"/>"#,
+            points_str(points)
+        );
+    }
+
+    fn polygon(&mut self, points: &[(f64, f64)], color: &str, opacity: f64) {
+        _ = writeln!(
+            self.body,
+            r#"<polygon points="{}" fill="{color}" fill-opacity="{opacity}" stroke="{color}"/>"#,
+            points_str(points)
+        );
+    }
+
+    fn text(&mut self, x: f64, y: f64, anchor: &str, s: &str) {
+        _ = writeln!(
+            self.body,
+            r#"<text x="{x:.1}" y="{y:.1}" text-anchor="{anchor}">{}</text>"#,
+            escape(s)
+        );
+    }
+
+    fn title(&mut self, x: f64, y: f64, s: &str) {
+        _ = writeln!(
+            self.body,
+            r#"<text x="{x:.1}" y="{y:.1}" text-anchor="middle" font-size="16" font-weight="bold">{}</text>"#,
+            escape(s)
+        );
+    }
+
+    fn finish(self) -> String {
+        format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="12">
+<rect width="100%" height="100%" fill="#fff"/>
+{}</svg>
+"##,
+            self.body,
+            w = self.width,
+            h = self.height,
+        )
+    }
+}
+
+fn points_str(points: &[(f64, f64)]) -> String {
+    points
+        .iter()
+        .map(|(x, y)| format!("{x:.1},{y:.1}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Escape text so that it can be used in XML or HTML.
+pub fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,66 @@
+use std::fs;
+
+use super::{ProcSample, ThreadSample};
+
+/// Get the current state of the process from `/proc`.
+pub fn sample(pid: u32) -> Option<ProcSample> {
+    let status = fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    let rss = status
+        .lines()
+        .find_map(|l| l.strip_prefix("VmRSS:"))
+        .and_then(|v| v.trim().strip_suffix("kB"))
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .map_or(0, |v| v * 1024);
+
+    let mut threads = vec![];
+    for task in fs::read_dir(format!("/proc/{pid}/task")).ok()?.flatten() {
+        let Ok(tid) = task.file_name().to_string_lossy().parse() else {
+            continue;
+        };
+        let path = task.path();
+        let Ok(stat) = fs::read_to_string(path.join("stat")) else {
+            continue;
+        };
+        // The name may contain spaces and parentheses so the fields are
+        // after the last `)`.
+        let Some((_, fields)) = stat.rsplit_once(')') else {
+            continue;
+        };
+        let fields: Vec<_> = fields.split_whitespace().collect();
+        let ticks = |i: usize| {
+            fields
+                .get(i)
+                .and_then(|f| f.parse::<u64>().ok())
+                .unwrap_or(0)
+        };
+        let name = fs::read_to_string(path.join("comm")).unwrap_or_default();
+        threads.push(ThreadSample {
+            tid,
+            name: name.trim().to_string(),
+            // utime and stime
+            cpu_ticks: ticks(11) + ticks(12),
+        });
+    }
+
+    let io = fs::read_to_string(format!("/proc/{pid}/io")).unwrap_or_default();
+    let io_field = |name: &str| {
+        io.lines()
+            .find_map(|l| l.strip_prefix(name))
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(0)
+    };
+
+    Some(ProcSample {
+        rss,
+        threads,
+        read: io_field("rchar:"),
+        written: io_field("wchar:"),
+    })
+}
+
+pub fn ticks_per_sec() -> f64 {
+    match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        t if t > 0 => t as f64,
+        _ => 100.,
+    }
+}
@@ -0,0 +1,52 @@
+#[cfg(target_os = "linux")]
+mod linux;
+
+/// State of running process at single point in time.
+#[derive(Debug, Clone)]
+pub struct ProcSample {
+    /// Resident set size in bytes.
+    pub rss: usize,
+    pub threads: Vec<ThreadSample>,
+    /// Total number of bytes read.
+    pub read: u64,
+    /// Total number of bytes written.
+    pub written: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ThreadSample {
+    pub tid: u32,
+    pub name: String,
+    /// Total CPU time of the thread in clock ticks.
+    pub cpu_ticks: u64,
+}
+
+impl ProcSample {
+    /// Get the current state of the running process. Returns [`None`] if it
+    /// cannot be obtained on this platform.
+    pub fn get(pid: u32) -> Option<Self> {
+        #[cfg(target_os = "linux")]
+        {
+            linux::sample(pid)
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            _ = pid;
+            None
+        }
+    }
+}
+
+/// Number of clock ticks per second used for CPU times.
+pub fn ticks_per_sec() -> f64 {
+    #[cfg(target_os = "linux")]
+    {
+        linux::ticks_per_sec()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        100.
+    }
+}
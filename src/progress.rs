@@ -0,0 +1,104 @@
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use termal::{codes, formatmc};
+
+use crate::{com_measure::ComMeasure, get_dur_string, get_mem_string, stats};
+
+/// Live status line shown on stderr during the measurements.
+#[derive(Debug)]
+pub struct Progress {
+    enabled: bool,
+    color: bool,
+    start: Instant,
+    total: Option<usize>,
+    max_time: Option<Duration>,
+    shown: bool,
+}
+
+impl Progress {
+    /// Create new progress line. `total` is the maximum number of runs and
+    /// `max_time` is the time limit of the measurement if known.
+    pub fn new(
+        enabled: bool,
+        color: bool,
+        total: Option<usize>,
+        max_time: Option<Duration>,
+    ) -> Self {
+        Self {
+            enabled,
+            color,
+            start: Instant::now(),
+            total,
+            max_time,
+            shown: false,
+        }
+    }
+
+    /// Show the progress after the given run.
+    pub fn update(&mut self, run: usize, stats: &ComMeasure) {
+        if !self.enabled {
+            return;
+        }
+
+        let elapsed = self.start.elapsed();
+        let per_run = elapsed / run.max(1) as u32;
+        let eta = self
+            .total
+            .map(|t| per_run * t.saturating_sub(run) as u32)
+            .into_iter()
+            .chain(self.max_time.map(|t| t.saturating_sub(elapsed)))
+            .min();
+
+        let samples = stats.samples();
+        let times = samples.times_ns();
+        let dur = |v: f64| get_dur_string(Duration::from_nanos(v as u64));
+
+        let mut line = match self.total {
+            Some(t) => {
+                formatmc!(self.color, "{'dm}run {'w bold}{run}/{t}{'_}")
+            }
+            None => formatmc!(self.color, "{'dm}run {'w bold}{run}{'_}"),
+        };
+        if let Some(eta) = eta {
+            line += &formatmc!(
+                self.color,
+                "{'dm} · ETA {'_}{}",
+                get_dur_string(eta)
+            );
+        }
+        if !times.is_empty() {
+            line += &formatmc!(
+                self.color,
+                "{'dm} · mean {'m bold}{} ± {}{'_}",
+                dur(stats::mean(&times)),
+                dur(stats::std_dev(&times)),
+            );
+        }
+        if let Some(t) = samples.times.last() {
+            line += &formatmc!(
+                self.color,
+                "{'dm} · last {'_}{}",
+                get_dur_string(*t)
+            );
+            if let Some(m) = samples.memories.last() {
+                line += &format!(", {}", get_mem_string(*m));
+            }
+        }
+
+        eprint!("\r{}{line}", codes::ERASE_LINE);
+        _ = io::stderr().flush();
+        self.shown = true;
+    }
+
+    /// Remove the progress line so that other output can be printed.
+    pub fn clear(&mut self) {
+        if self.shown {
+            eprint!("\r{}", codes::ERASE_LINE);
+            _ = io::stderr().flush();
+            self.shown = false;
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+};
+
+use crate::{
+    err::{Error, Result, file_create},
+    tmp_dir::create_temp,
+};
+
+/// File that receives output of the program for a single run.
+#[derive(Debug)]
+pub struct RunOutput {
+    path: String,
+    kind: RunOutputKind,
+}
+
+#[derive(Debug)]
+enum RunOutputKind {
+    /// File used only by this run.
+    PerRun,
+    /// File shared by all runs. Contains the original length of the file.
+    Shared(u64),
+    /// Temporary file that is removed when dropped.
+    Temp,
+}
+
+impl RunOutput {
+    /// Placeholder that is replaced with the number of the run.
+    pub const RUN_PLACEHOLDER: &str = "{run}";
+
+    /// Open the output file for the given run. If the path contains
+    /// `{run}`, each run has its own file. Otherwise output of all the runs is
+    /// appended to the same file.
+    pub fn open(path: &str, run: usize) -> Result<(Self, File)> {
+        if path.contains(Self::RUN_PLACEHOLDER) {
+            let path = path.replace(Self::RUN_PLACEHOLDER, &run.to_string());
+            let file = file_create(&path)?;
+            let res = Self {
+                path,
+                kind: RunOutputKind::PerRun,
+            };
+            return Ok((res, file));
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| Error::FailedToWrite(path.to_string(), e))?;
+        let len = file
+            .metadata()
+            .map_err(|e| Error::FailedToWrite(path.to_string(), e))?
+            .len();
+        let res = Self {
+            path: path.to_string(),
+            kind: RunOutputKind::Shared(len),
+        };
+        Ok((res, file))
+    }
+
+    /// Create new temporary file with the given name.
+    pub fn temp(name: &str) -> Result<(Self, File)> {
+        let mut opts = OpenOptions::new();
+        opts.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut opts, 0o600);
+        let (path, file) =
+            create_temp(name, |p| opts.open(p)).map_err(|e| {
+                Error::FailedToWrite(
+                    env::temp_dir().to_string_lossy().into(),
+                    e,
+                )
+            })?;
+        let res = Self {
+            path: path.to_string_lossy().into_owned(),
+            kind: RunOutputKind::Temp,
+        };
+        Ok((res, file))
+    }
+
+    /// Checks whether the file is temporary and should be always discarded.
+    pub fn is_temp(&self) -> bool {
+        matches!(self.kind, RunOutputKind::Temp)
+    }
+
+    /// Read the output of this run.
+    pub fn read(&self) -> Result<Vec<u8>> {
+        let mut data = fs::read(&self.path)
+            .map_err(|e| Error::FailedToOpen(self.path.clone(), e))?;
+        if let RunOutputKind::Shared(len) = self.kind {
+            data.drain(..(len as usize).min(data.len()));
+        }
+        Ok(data)
+    }
+
+    /// Remove the output of this run. Temporary file is removed when
+    /// dropped.
+    pub fn discard(self) -> Result<()> {
+        let map_err = |e| Error::FailedToWrite(self.path.clone(), e);
+        match self.kind {
+            RunOutputKind::Temp => Ok(()),
+            RunOutputKind::PerRun => {
+                fs::remove_file(&self.path).map_err(map_err)
+            }
+            RunOutputKind::Shared(len) => OpenOptions::new()
+                .write(true)
+                .open(&self.path)
+                .and_then(|f| f.set_len(len))
+                .map_err(map_err),
+        }
+    }
+}
+
+impl Drop for RunOutput {
+    fn drop(&mut self) {
+        if self.is_temp() {
+            _ = fs::remove_file(&self.path);
+        }
+    }
+}
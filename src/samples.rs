@@ -0,0 +1,69 @@
+use std::{fs, io::Write, time::Duration};
+
+use crate::{
+    com_measure::RunRecord,
+    err::{Error, Result, file_create},
+};
+
+/// Measured samples of time and memory.
+#[derive(Debug, Default, Clone)]
+pub struct Samples {
+    pub times: Vec<Duration>,
+    pub memories: Vec<usize>,
+}
+
+impl Samples {
+    const HEADER: &str = "time_ns,memory";
+
+    /// Load samples saved with [`Samples::save`].
+    pub fn load(path: &str) -> Result<Self> {
+        let data = fs::read_to_string(path)
+            .map_err(|e| Error::FailedToOpen(path.to_string(), e))?;
+
+        let mut res = Self::default();
+        for (i, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line == Self::HEADER {
+                continue;
+            }
+
+            let err = || Error::InvalidSamplesFile(path.to_string(), i + 1);
+            let (time, mem) = line.split_once(',').ok_or_else(err)?;
+            let time = time.trim().parse().map_err(|_| err())?;
+            res.times.push(Duration::from_nanos(time));
+            let mem = mem.trim();
+            if !mem.is_empty() {
+                res.memories.push(mem.parse().map_err(|_| err())?);
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Save the samples of the given runs to the file as csv. There is one
+    /// row for each run. Missing memory is left empty.
+    pub fn save(runs: &[RunRecord], path: &str) -> Result<()> {
+        let mut s = format!("{}\n", Self::HEADER);
+        for r in runs {
+            s += &format!(
+                "{},{}\n",
+                r.time.as_nanos(),
+                r.memory.map(|m| m.to_string()).unwrap_or_default(),
+            );
+        }
+
+        file_create(path)?
+            .write_all(s.as_bytes())
+            .map_err(|e| Error::FailedToWrite(path.to_string(), e))
+    }
+
+    /// Times in nanoseconds.
+    pub fn times_ns(&self) -> Vec<f64> {
+        self.times.iter().map(|t| t.as_nanos() as f64).collect()
+    }
+
+    /// Memory in bytes.
+    pub fn memories_f64(&self) -> Vec<f64> {
+        self.memories.iter().map(|m| *m as f64).collect()
+    }
+}
@@ -0,0 +1,75 @@
+use std::{io, mem, os::unix::process::CommandExt, process::Command};
+
+use libc::{
+    CPU_SET, CPU_SETSIZE, PRIO_PROCESS, SCHED_BATCH, SCHED_FIFO, SCHED_IDLE,
+    cpu_set_t, sched_get_priority_min, sched_param, sched_setaffinity,
+    sched_setscheduler, setpriority,
+};
+
+use crate::{
+    cli::SchedPolicy,
+    err::{Error, Result},
+};
+
+pub fn set_sched(
+    cmd: &mut Command,
+    cpus: Option<&[usize]>,
+    nice: Option<i32>,
+    policy: Option<SchedPolicy>,
+) -> Result<()> {
+    let cpus = cpus.map(cpu_set).transpose()?;
+    let policy = policy.map(|p| {
+        let policy = match p {
+            SchedPolicy::Fifo => SCHED_FIFO,
+            SchedPolicy::Batch => SCHED_BATCH,
+            SchedPolicy::Idle => SCHED_IDLE,
+        };
+        let prio = if p == SchedPolicy::Fifo {
+            unsafe { sched_get_priority_min(policy) }
+        } else {
+            0
+        };
+        (policy, prio)
+    });
+
+    // Only async-signal-safe functions may be called after fork.
+    unsafe {
+        cmd.pre_exec(move || {
+            if let Some(set) = &cpus
+                && sched_setaffinity(0, mem::size_of::<cpu_set_t>(), set) != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+
+            if let Some((policy, prio)) = policy {
+                let param = sched_param {
+                    sched_priority: prio,
+                };
+                if sched_setscheduler(0, policy, &param) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+
+            if let Some(nice) = nice
+                && setpriority(PRIO_PROCESS, 0, nice) != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(())
+        });
+    }
+
+    Ok(())
+}
+
+fn cpu_set(cpus: &[usize]) -> Result<cpu_set_t> {
+    let mut set: cpu_set_t = unsafe { mem::zeroed() };
+    for &c in cpus {
+        if c >= CPU_SETSIZE as usize {
+            return Err(Error::InvalidCpu(c));
+        }
+        unsafe { CPU_SET(c, &mut set) };
+    }
+    Ok(set)
+}
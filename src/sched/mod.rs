@@ -0,0 +1,29 @@
+use std::process::Command;
+
+use crate::{cli::SchedPolicy, err::Result};
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+/// Set the CPU affinity, niceness and scheduling policy of the program.
+pub fn set_sched(
+    cmd: &mut Command,
+    cpus: Option<&[usize]>,
+    nice: Option<i32>,
+    policy: Option<SchedPolicy>,
+) -> Result<()> {
+    if cpus.is_none() && nice.is_none() && policy.is_none() {
+        return Ok(());
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::set_sched(cmd, cpus, nice, policy)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        _ = cmd;
+        Err(crate::err::Error::Unsupported("--cpus, --nice and --sched"))
+    }
+}
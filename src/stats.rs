@@ -0,0 +1,524 @@
+use crate::rng::Rng;
+
+/// Arithmetic mean of the values. Returns `NaN` for no values.
+pub fn mean(v: &[f64]) -> f64 {
+    v.iter().sum::<f64>() / v.len() as f64
+}
+
+/// Sample standard deviation of the values.
+pub fn std_dev(v: &[f64]) -> f64 {
+    if v.len() < 2 {
+        return 0.;
+    }
+    let m = mean(v);
+    let var = v.iter().map(|x| (x - m) * (x - m)).sum::<f64>()
+        / (v.len() - 1) as f64;
+    var.sqrt()
+}
+
+/// Two sided critical value of Student's t-distribution for the given
+/// confidence level (`0` to `1`) with the given degrees of freedom.
+pub fn t_critical(confidence: f64, df: usize) -> f64 {
+    if df == 0 {
+        return f64::INFINITY;
+    }
+    // The p-value decreases with the value, so it can be found by bisection.
+    let alpha = 1. - confidence;
+    let (mut lo, mut hi) = (0., 1e7);
+    for _ in 0..64 {
+        let mid = (lo + hi) / 2.;
+        if student_t_p(mid, df as f64) > alpha {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo + hi) / 2.
+}
+
+/// Half width of the confidence interval of the mean with the given
+/// confidence level (`0` to `1`) relative to the mean.
+pub fn rel_ci(v: &[f64], confidence: f64) -> f64 {
+    if v.len() < 2 {
+        return f64::INFINITY;
+    }
+    let half = t_critical(confidence, v.len() - 1) * std_dev(v)
+        / (v.len() as f64).sqrt();
+    half / mean(v).abs()
+}
+
+/// Median of the values. The values will be sorted. Returns `NaN` for no
+/// values.
+pub fn median(v: &mut [f64]) -> f64 {
+    v.sort_by(f64::total_cmp);
+    percentile(v, 0.5)
+}
+
+/// Get the given percentile (`0` to `1`) from sorted values. Values between
+/// samples are interpolated.
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return f64::NAN;
+    }
+    let pos = p.clamp(0., 1.) * (sorted.len() - 1) as f64;
+    let lo = pos.floor() as usize;
+    let hi = pos.ceil() as usize;
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f64)
+}
+
+/// Estimated value with confidence interval.
+#[derive(Debug, Copy, Clone)]
+pub struct Estimate {
+    pub value: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Estimate {
+    /// Half of the width of the confidence interval.
+    pub fn half_width(&self) -> f64 {
+        (self.high - self.low) / 2.
+    }
+}
+
+/// Number of resamples used in bootstrap.
+const BOOTSTRAP_RESAMPLES: usize = 1000;
+
+/// Estimate the statistic with confidence interval with the given confidence
+/// level (`0` to `1`) using bootstrap resampling. Returns [`None`] if there
+/// are no samples.
+pub fn bootstrap(
+    samples: &[f64],
+    stat: impl Fn(&mut [f64]) -> f64,
+    confidence: f64,
+    rng: &mut Rng,
+) -> Option<Estimate> {
+    if samples.is_empty() {
+        return None;
+    }
+
+    let value = stat(&mut samples.to_vec());
+    let mut buf = vec![0.; samples.len()];
+    let mut stats: Vec<_> = (0..BOOTSTRAP_RESAMPLES)
+        .map(|_| {
+            for b in &mut buf {
+                *b = samples[rng.below(samples.len())];
+            }
+            stat(&mut buf)
+        })
+        .collect();
+    stats.sort_by(f64::total_cmp);
+
+    let alpha = (1. - confidence) / 2.;
+    Some(Estimate {
+        value,
+        low: percentile(&stats, alpha),
+        high: percentile(&stats, 1. - alpha),
+    })
+}
+
+/// Result of statistical test.
+#[derive(Debug, Copy, Clone)]
+pub struct TestResult {
+    /// Value of the test statistic.
+    pub statistic: f64,
+    /// Two sided p-value.
+    pub p_value: f64,
+    /// Effect size.
+    pub effect: f64,
+}
+
+/// Welch's t-test of difference of means of `b` and `a`. The effect size is
+/// Cohen's d. If both of the sample sets have zero variance and different
+/// means, the statistic and the effect size are NaN and the p-value is zero.
+/// Returns [`None`] if any of the sample sets has less than two samples.
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> Option<TestResult> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let (ma, mb) = (mean(a), mean(b));
+    let (va, vb) = (std_dev(a).powi(2), std_dev(b).powi(2));
+    let diff = mb - ma;
+
+    let pooled = ((va + vb) / 2.).sqrt();
+    if pooled == 0. {
+        let (effect, p_value) =
+            if diff == 0. { (0., 1.) } else { (f64::NAN, 0.) };
+        return Some(TestResult {
+            statistic: effect,
+            p_value,
+            effect,
+        });
+    }
+    let effect = diff / pooled;
+
+    let se2 = va / na + vb / nb;
+
+    let statistic = diff / se2.sqrt();
+    let df = se2 * se2
+        / ((va / na).powi(2) / (na - 1.) + (vb / nb).powi(2) / (nb - 1.));
+    Some(TestResult {
+        statistic,
+        p_value: student_t_p(statistic, df),
+        effect,
+    })
+}
+
+/// Mann-Whitney U test of `b` against `a`. The statistic is U of `b` and the
+/// effect size is rank-biserial correlation (positive if values in `b` tend
+/// to be larger). Normal approximation with tie correction is used for the
+/// p-value. Returns [`None`] if any of the sample sets is empty.
+pub fn mann_whitney_u(a: &[f64], b: &[f64]) -> Option<TestResult> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+
+    let mut all: Vec<_> = a
+        .iter()
+        .map(|v| (*v, false))
+        .chain(b.iter().map(|v| (*v, true)))
+        .collect();
+    all.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    let n = all.len() as f64;
+    let mut rank_sum_b = 0.;
+    let mut ties = 0.;
+    let mut i = 0;
+    while i < all.len() {
+        let mut j = i;
+        while j < all.len() && all[j].0 == all[i].0 {
+            j += 1;
+        }
+        let rank = (i + j + 1) as f64 / 2.;
+        rank_sum_b += rank * all[i..j].iter().filter(|v| v.1).count() as f64;
+        let t = (j - i) as f64;
+        ties += t * t * t - t;
+        i = j;
+    }
+
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let statistic = rank_sum_b - nb * (nb + 1.) / 2.;
+    let effect = 2. * statistic / (na * nb) - 1.;
+
+    let mu = na * nb / 2.;
+    let sigma =
+        (na * nb / 12. * ((n + 1.) - ties / (n * (n - 1.)).max(1.))).sqrt();
+    let p_value = if sigma == 0. {
+        1.
+    } else {
+        let d = (statistic - mu).abs() - 0.5;
+        normal_p(d.max(0.) / sigma)
+    };
+
+    Some(TestResult {
+        statistic,
+        p_value,
+        effect,
+    })
+}
+
+/// Detect step changes in the series with binary segmentation. Returns the
+/// indices where new segments start. Split is accepted if it reduces the sum
+/// of squared errors by more than BIC penalty (noise is estimated from the
+/// differences of consecutive values) and the mean changes at least by
+/// `min_step` relative to the mean before the change.
+pub fn change_points(values: &[f64], min_step: f64) -> Vec<usize> {
+    if values.len() < 2 {
+        return vec![];
+    }
+
+    let mut diffs: Vec<_> =
+        values.windows(2).map(|w| (w[1] - w[0]).abs()).collect();
+    let sigma = median(&mut diffs) / (0.6745 * std::f64::consts::SQRT_2);
+    let penalty = 2. * sigma * sigma * (values.len() as f64).ln();
+
+    let mut res = vec![];
+    segment(values, 0, penalty, min_step, &mut res);
+    res.sort_unstable();
+    res
+}
+
+fn segment(
+    values: &[f64],
+    offset: usize,
+    penalty: f64,
+    min_step: f64,
+    res: &mut Vec<usize>,
+) {
+    let cost = |v: &[f64]| {
+        let m = mean(v);
+        v.iter().map(|x| (x - m).powi(2)).sum::<f64>()
+    };
+
+    let Some((k, split)) = (1..values.len())
+        .map(|k| (k, cost(&values[..k]) + cost(&values[k..])))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+    else {
+        return;
+    };
+
+    let (before, after) = (mean(&values[..k]), mean(&values[k..]));
+    if cost(values) - split <= penalty
+        || (after - before).abs() <= before.abs() * min_step
+    {
+        return;
+    }
+
+    res.push(offset + k);
+    segment(&values[..k], offset, penalty, min_step, res);
+    segment(&values[k..], offset + k, penalty, min_step, res);
+}
+
+/// Two sided p-value of the standard normal distribution.
+pub fn normal_p(z: f64) -> f64 {
+    erfc(z.abs() / std::f64::consts::SQRT_2).min(1.)
+}
+
+/// Two sided p-value of Student's t-distribution with `df` degrees of
+/// freedom.
+pub fn student_t_p(t: f64, df: f64) -> f64 {
+    if !t.is_finite() {
+        return 0.;
+    }
+    inc_beta(df / 2., 0.5, df / (df + t * t)).clamp(0., 1.)
+}
+
+/// Complementary error function with fractional error less than `1.2e-7`.
+fn erfc(x: f64) -> f64 {
+    const COEFS: [f64; 10] = [
+        -1.26551223,
+        1.00002368,
+        0.37409196,
+        0.09678418,
+        -0.18628806,
+        0.27886807,
+        -1.13520398,
+        1.48851587,
+        -0.82215223,
+        0.17087277,
+    ];
+    let z = x.abs();
+    let t = 1. / (1. + 0.5 * z);
+    let poly = COEFS.iter().rev().fold(0., |acc, c| acc * t + c);
+    let r = t * (-z * z + poly).exp();
+    if x >= 0. { r } else { 2. - r }
+}
+
+/// Logarithm of the gamma function (Lanczos approximation).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut ser = 1.000000000190015;
+    for (i, c) in COEFS.iter().enumerate() {
+        ser += c / (x + 1. + i as f64);
+    }
+    -tmp + (2.5066282746310005 * ser / x).ln()
+}
+
+/// Regularized incomplete beta function `I_x(a, b)`.
+fn inc_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0. {
+        return 0.;
+    }
+    if x >= 1. {
+        return 1.;
+    }
+
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b)
+        + a * x.ln()
+        + b * (1. - x).ln())
+    .exp();
+    if x < (a + 1.) / (a + b + 2.) {
+        front * beta_cf(a, b, x) / a
+    } else {
+        1. - front * beta_cf(b, a, 1. - x) / b
+    }
+}
+
+/// Continued fraction for the incomplete beta function (modified Lentz's
+/// method).
+fn beta_cf(a: f64, b: f64, x: f64) -> f64 {
+    const MAX_ITER: usize = 200;
+    const EPS: f64 = 1e-14;
+    const TINY: f64 = 1e-300;
+
+    let clamp = |v: f64| if v.abs() < TINY { TINY } else { v };
+
+    let mut c = 1.;
+    let mut d = 1. / clamp(1. - (a + b) * x / (a + 1.));
+    let mut h = d;
+    for m in 1..=MAX_ITER {
+        let m = m as f64;
+        let m2 = 2. * m;
+
+        let aa = m * (b - m) * x / ((a + m2 - 1.) * (a + m2));
+        d = 1. / clamp(1. + aa * d);
+        c = clamp(1. + aa / c);
+        h *= d * c;
+
+        let aa = -(a + m) * (a + b + m) * x / ((a + m2) * (a + m2 + 1.));
+        d = 1. / clamp(1. + aa * d);
+        c = clamp(1. + aa / c);
+        let del = d * c;
+        h *= del;
+
+        if (del - 1.).abs() < EPS {
+            break;
+        }
+    }
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, eps: f64) {
+        assert!(
+            (actual - expected).abs() <= eps,
+            "{actual} is not within {eps} of {expected}"
+        );
+    }
+
+    #[test]
+    fn test_mean_std_dev() {
+        let v = [2., 4., 4., 4., 5., 5., 7., 9.];
+        assert_close(mean(&v), 5., 1e-12);
+        assert_close(std_dev(&v), 2.138089935299395, 1e-12);
+        assert_eq!(std_dev(&[1.]), 0.);
+        assert!(mean(&[]).is_nan());
+    }
+
+    #[test]
+    fn test_median_percentile() {
+        assert_eq!(median(&mut [3., 1., 2.]), 2.);
+        assert_eq!(median(&mut [4., 1., 3., 2.]), 2.5);
+        assert_eq!(percentile(&[1., 2., 3., 4., 5.], 0.25), 2.);
+        assert_eq!(percentile(&[1., 2.], 2.), 2.);
+        assert!(percentile(&[], 0.5).is_nan());
+    }
+
+    #[test]
+    fn test_t_critical() {
+        assert_close(t_critical(0.95, 1), 12.706, 1e-3);
+        assert_close(t_critical(0.95, 10), 2.228, 1e-3);
+        assert_close(t_critical(0.95, 30), 2.042, 1e-3);
+        assert_close(t_critical(0.99, 5), 4.032, 1e-3);
+        assert_close(t_critical(0.95, 100_000), 1.960, 1e-3);
+        assert_eq!(t_critical(0.95, 0), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_rel_ci() {
+        let v = [9., 10., 11.];
+        assert_close(rel_ci(&v, 0.95), 4.303 / 3f64.sqrt() / 10., 1e-4);
+        assert!(rel_ci(&v, 0.99) > rel_ci(&v, 0.95));
+        assert_eq!(rel_ci(&[1.], 0.95), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_erfc() {
+        assert_close(erfc(0.), 1., 1e-7);
+        assert_close(erfc(1.), 0.15729920705028513, 1e-7);
+        assert_close(erfc(-1.), 1.842700792949715, 1e-7);
+        assert_close(erfc(2.), 0.004677734981047265, 1e-8);
+    }
+
+    #[test]
+    fn test_ln_gamma() {
+        assert_close(ln_gamma(1.), 0., 1e-10);
+        assert_close(ln_gamma(0.5), 0.5723649429247004, 1e-10);
+        assert_close(ln_gamma(10.), 12.801827480081467, 1e-10);
+    }
+
+    #[test]
+    fn test_inc_beta() {
+        assert_close(inc_beta(1., 1., 0.3), 0.3, 1e-12);
+        // P(Binomial(4, 0.5) >= 2)
+        assert_close(inc_beta(2., 3., 0.5), 11. / 16., 1e-12);
+        assert_eq!(inc_beta(2., 3., 0.), 0.);
+        assert_eq!(inc_beta(2., 3., 1.), 1.);
+    }
+
+    #[test]
+    fn test_p_values() {
+        assert_close(normal_p(1.959964), 0.05, 1e-6);
+        assert_close(normal_p(-1.959964), 0.05, 1e-6);
+        assert_close(student_t_p(2.228, 10.), 0.05, 1e-4);
+        assert_close(student_t_p(2., 8.), 0.08051623787888036, 1e-9);
+        assert_eq!(student_t_p(f64::INFINITY, 8.), 0.);
+    }
+
+    #[test]
+    fn test_welch_t_test() {
+        let a = [1., 2., 3., 4., 5.];
+        let b = [3., 4., 5., 6., 7.];
+        let res = welch_t_test(&a, &b).unwrap();
+        assert_close(res.statistic, 2., 1e-12);
+        assert_close(res.p_value, 0.08051623787888036, 1e-9);
+        assert_close(res.effect, 2. / 2.5f64.sqrt(), 1e-12);
+
+        let res = welch_t_test(&[1., 1.], &[1., 1.]).unwrap();
+        assert_eq!(res.p_value, 1.);
+        assert_eq!(res.effect, 0.);
+        let res = welch_t_test(&[1., 1.], &[2., 2.]).unwrap();
+        assert_eq!(res.p_value, 0.);
+        assert!(res.statistic.is_nan() && res.effect.is_nan());
+        assert!(welch_t_test(&[1.], &b).is_none());
+    }
+
+    #[test]
+    fn test_mann_whitney_u_ties() {
+        let a = [1., 2., 2., 3.];
+        let b = [2., 3., 4., 5.];
+        let res = mann_whitney_u(&a, &b).unwrap();
+        assert_close(res.statistic, 13.5, 1e-12);
+        assert_close(res.effect, 0.6875, 1e-12);
+        // Normal approximation with tie and continuity correction.
+        assert_close(res.p_value, 0.13665824773814753, 1e-6);
+
+        let res = mann_whitney_u(&[1., 1.], &[1., 1.]).unwrap();
+        assert_eq!(res.p_value, 1.);
+        assert_eq!(res.effect, 0.);
+        assert!(mann_whitney_u(&[], &b).is_none());
+    }
+
+    #[test]
+    fn test_bootstrap() {
+        let samples: Vec<_> = (1..=10).map(f64::from).collect();
+        let mut rng = Rng::new(1);
+        let est = bootstrap(&samples, |v| mean(v), 0.95, &mut rng).unwrap();
+        assert_eq!(est.value, 5.5);
+        assert!(est.low < 5.5 && 5.5 < est.high);
+        assert!(est.low >= 1. && est.high <= 10.);
+
+        let est = bootstrap(&[3.; 5], median, 0.95, &mut rng).unwrap();
+        assert_eq!((est.low, est.value, est.high), (3., 3., 3.));
+        assert!(bootstrap(&[], median, 0.95, &mut rng).is_none());
+    }
+
+    #[test]
+    fn test_change_points() {
+        let step = [10., 10.1, 9.9, 10., 10.1, 20., 20.1, 19.9, 20., 20.1];
+        assert_eq!(change_points(&step, 0.05), vec![5]);
+
+        let small = [10., 10.1, 9.9, 10., 10.1, 10.3, 10.4, 10.2, 10.3, 10.4];
+        assert!(change_points(&small, 0.1).is_empty());
+
+        let two = [1., 1.1, 0.9, 5., 5.1, 4.9, 1., 1.1, 0.9, 1.];
+        assert_eq!(change_points(&two, 0.05), vec![3, 6]);
+
+        assert!(change_points(&[1.; 8], 0.05).is_empty());
+        assert!(change_points(&[1.], 0.05).is_empty());
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{
+    com_measure::ComMeasure, comparison::Comparison, config::Benchmark,
+    err::Error, history::History, json::JsonObject,
+};
+
+/// Minimal relative increase of time or memory against the last recorded
+/// session that is reported as regression. Smaller changes are usually
+/// caused by the environment even if they are significant.
+const MIN_REGRESSION: f64 = 0.05;
+
+/// Status of benchmark in suite. Ordered from the best to the worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    Ok,
+    Regressed,
+    Exceeded,
+    Failed,
+}
+
+/// Result of single benchmark (or variant of benchmark) in suite.
+pub struct SuiteResult {
+    pub name: String,
+    pub status: Status,
+    /// Description of the problems of the benchmark.
+    pub problems: Vec<String>,
+    pub stats: Option<ComMeasure>,
+    /// Comparison with the last recorded session of the benchmark.
+    pub baseline: Option<Comparison>,
+}
+
+impl Status {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Regressed => "regressed",
+            Self::Exceeded => "exceeded",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+impl SuiteResult {
+    /// Evaluate the results of the benchmark. The results are compared
+    /// with the last session recorded under `name` with significance level
+    /// `alpha`.
+    pub fn new(
+        name: String,
+        bench: &Benchmark,
+        stats: ComMeasure,
+        hist: &History,
+        alpha: f64,
+    ) -> Self {
+        let mut status = Status::Ok;
+        let mut problems = vec![];
+        let mut problem = |s: Status, p: String| {
+            status = status.max(s);
+            problems.push(p);
+        };
+
+        let failed = stats.attempts() - stats.success();
+        if failed != 0 {
+            problem(
+                Status::Failed,
+                format!("{failed} of {} runs failed.", stats.attempts()),
+            );
+        }
+        for p in bench.check(&stats) {
+            problem(Status::Exceeded, p);
+        }
+
+        let last = hist.sessions(&name).ok().and_then(|s| s.last().copied());
+        let baseline = last.map(|last| {
+            Comparison::new(
+                format!("{name}: current vs last session"),
+                last.stats().samples(),
+                stats.samples(),
+                alpha,
+            )
+        });
+        for (q, qc) in baseline
+            .iter()
+            .flat_map(|b| [("Time", &b.time), ("Memory", &b.memory)])
+        {
+            if let Some(qc) = qc
+                && qc.significant
+                && qc.change >= MIN_REGRESSION
+            {
+                problem(
+                    Status::Regressed,
+                    format!(
+                        "{q} is {:.1}% higher than in the last recorded \
+                        session.",
+                        qc.change * 100.
+                    ),
+                );
+            }
+        }
+
+        Self {
+            name,
+            status,
+            problems,
+            stats: Some(stats),
+            baseline,
+        }
+    }
+
+    /// Create result of benchmark that couldn't be measured.
+    pub fn failed(name: String, err: &Error) -> Self {
+        Self {
+            name,
+            status: Status::Failed,
+            problems: vec![err.to_string()],
+            stats: None,
+            baseline: None,
+        }
+    }
+
+    /// Get the relative changes of time and memory against the last
+    /// recorded session.
+    pub fn changes(&self) -> [(&'static str, Option<f64>); 2] {
+        let b = self.baseline.as_ref();
+        [
+            ("time", b.and_then(|b| b.time.as_ref()).map(|c| c.change)),
+            (
+                "memory",
+                b.and_then(|b| b.memory.as_ref()).map(|c| c.change),
+            ),
+        ]
+    }
+
+    pub fn to_json(&self) -> JsonObject {
+        JsonObject::new()
+            .field("name", &self.name)
+            .field("status", self.status.name())
+            .field("problems", &self.problems)
+            .field("stats", self.stats.as_ref().map(|s| s.to_json()))
+            .field("baseline", self.baseline.as_ref().map(|b| b.to_json()))
+    }
+}
@@ -0,0 +1,79 @@
+use std::{
+    env,
+    fs::{self, DirBuilder},
+    io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use crate::{
+    err::{Error, Result},
+    rng::Rng,
+};
+
+/// Maximum number of attempts to create temporary file or directory with
+/// unique name.
+const MAX_ATTEMPTS: usize = 100;
+
+/// Temporary directory that is removed when dropped.
+#[derive(Debug)]
+pub struct TmpDir {
+    path: PathBuf,
+}
+
+impl TmpDir {
+    /// Placeholder that is replaced with the path to the directory.
+    pub const PLACEHOLDER: &str = "{tmpdir}";
+
+    /// Create new empty temporary directory for the given run.
+    pub fn new(run: usize) -> Result<Self> {
+        let mut builder = DirBuilder::new();
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        let (path, _) =
+            create_temp(&format!("run{run}"), |p| builder.create(p)).map_err(
+                |e| {
+                    Error::FailedToCreateDir(
+                        env::temp_dir().to_string_lossy().into(),
+                        e,
+                    )
+                },
+            )?;
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Replace all occurences of `{tmpdir}` with the path to the directory.
+    pub fn substitute(&self, s: &str) -> String {
+        s.replace(Self::PLACEHOLDER, &self.path.to_string_lossy())
+    }
+}
+
+impl Drop for TmpDir {
+    fn drop(&mut self) {
+        _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Create new file or directory with random name in the system temporary
+/// directory. `create` must fail if the path already exists, so that files
+/// created by others (e.g. symlinks) are never used.
+pub fn create_temp<T>(
+    name: &str,
+    mut create: impl FnMut(&Path) -> io::Result<T>,
+) -> io::Result<(PathBuf, T)> {
+    let mut rng =
+        Rng::new(Rng::time_seed() ^ (u64::from(process::id()) << 32));
+    let dir = env::temp_dir();
+    for _ in 0..MAX_ATTEMPTS {
+        let path = dir.join(format!("mproc-{name}-{:016x}", rng.next_u64()));
+        match create(&path) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+            r => return r.map(|r| (path, r)),
+        }
+    }
+    Err(io::ErrorKind::AlreadyExists.into())
+}
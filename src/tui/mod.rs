@@ -0,0 +1,322 @@
+use std::{
+    collections::HashMap,
+    io::{self, IsTerminal, Write},
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use termal::{
+    codes, formatmc, move_to,
+    raw::{
+        Terminal, disable_raw_mode, enable_raw_mode,
+        events::{Event, KeyCode, Modifiers},
+        term_size,
+    },
+};
+
+use crate::{
+    err::{Error, Result},
+    get_dur_string, get_mem_string,
+    measurement::Measurement,
+    proc_sample::{ProcSample, ThreadSample, ticks_per_sec},
+};
+
+/// How often is the dashboard updated.
+const TICK: Duration = Duration::from_millis(100);
+/// Height of the charts in rows.
+const CHART_HEIGHT: usize = 5;
+/// Width of the CPU usage bars.
+const CPU_BAR_WIDTH: usize = 20;
+/// Characters used to draw fractions of chart cell in eights.
+const CHART_PARTS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Live statistics of the currently running process.
+#[derive(Debug, Default)]
+struct RunState {
+    /// RSS at the given time since the start of the process.
+    rss: Vec<(Duration, usize)>,
+    peak_rss: usize,
+    /// Threads with their CPU usage (`1` is one whole CPU).
+    threads: Vec<(ThreadSample, f64)>,
+    read_rate: f64,
+    write_rate: f64,
+    last: Option<(Instant, ProcSample)>,
+}
+
+/// Full screen live view of the measured program.
+pub struct Dashboard {
+    total: Option<usize>,
+    color: bool,
+    term: Terminal,
+    last_render: Option<Instant>,
+    quit: bool,
+    memory_curve: Vec<(Duration, usize)>,
+}
+
+impl Dashboard {
+    /// Switch the terminal to the dashboard. The terminal is restored when
+    /// the dashboard is dropped.
+    pub fn new(total: Option<usize>, color: bool) -> Result<Self> {
+        if !io::stderr().is_terminal() {
+            return Err(Error::NotTerminal("--tui"));
+        }
+
+        enable_raw_mode()?;
+        eprint!("{}{}", codes::ENABLE_ALTERNATIVE_BUFFER, codes::HIDE_CURSOR);
+        _ = io::stderr().flush();
+
+        Ok(Self {
+            total,
+            color,
+            term: Terminal::stdio(),
+            last_render: None,
+            quit: false,
+            memory_curve: vec![],
+        })
+    }
+
+    /// Checks whether the user requested to stop the measurements.
+    pub fn quit_requested(&self) -> bool {
+        self.quit
+    }
+
+    /// Get RSS of the last measured run at the given times since its start.
+    pub fn memory_curve(&self) -> &[(Duration, usize)] {
+        &self.memory_curve
+    }
+
+    /// Measure the command and show its live statistics. `title` describes
+    /// the command and `history` contains times of the previous runs.
+    pub fn measure(
+        &mut self,
+        cmd: &mut Command,
+        title: &str,
+        run: usize,
+        history: &[Duration],
+    ) -> Result<Measurement> {
+        let mut state = RunState::default();
+        let ticks = ticks_per_sec();
+        let start = Instant::now();
+        let mut kill = false;
+
+        let res = Measurement::measure_watched(cmd, TICK, &mut |pid| {
+            if let Some(s) = ProcSample::get(pid) {
+                state.update(s, start.elapsed(), ticks);
+            }
+            kill |= self.handle_input();
+            self.render(title, run, start.elapsed(), &state, history);
+            !kill
+        });
+
+        self.handle_input();
+        if self.last_render.is_none_or(|t| t.elapsed() >= TICK) {
+            self.render(title, run, start.elapsed(), &state, history);
+        }
+        self.memory_curve = state.rss;
+        res
+    }
+
+    /// Read the pressed keys. Returns `true` if the running process should
+    /// be killed.
+    fn handle_input(&mut self) -> bool {
+        let mut kill = false;
+        while let Ok(Some(ev)) = self.term.read_timeout(Duration::ZERO) {
+            let Event::KeyPress(key) = ev else {
+                continue;
+            };
+            match key.code {
+                KeyCode::Char('c')
+                    if key.modifiers.contains(Modifiers::CONTROL) =>
+                {
+                    kill = true;
+                    self.quit = true;
+                }
+                KeyCode::Char('q') => self.quit = true,
+                KeyCode::Char('k') => {
+                    kill = true;
+                    self.quit = true;
+                }
+                _ => {}
+            }
+        }
+        kill
+    }
+
+    fn render(
+        &mut self,
+        title: &str,
+        run: usize,
+        elapsed: Duration,
+        state: &RunState,
+        history: &[Duration],
+    ) {
+        self.last_render = Some(Instant::now());
+        let (width, height) = term_size()
+            .ok()
+            .map(|s| (s.char_width, s.char_height))
+            .filter(|(w, h)| *w != 0 && *h != 0)
+            .unwrap_or((80, 24));
+        let chart_width = width.saturating_sub(2).max(1);
+        let c = self.color;
+
+        let run = match self.total {
+            Some(t) => format!("{run}/{t}"),
+            None => run.to_string(),
+        };
+        let rss: Vec<_> = state.rss.iter().map(|r| r.1 as f64).collect();
+        let mut lines = vec![
+            formatmc!(c, "{'y bold}mproc {'_ gr}─ {'_}{title}"),
+            formatmc!(
+                c,
+                "{'dm}run {'w bold}{run}{'_ dm} · elapsed {'_}{}",
+                get_dur_string(elapsed)
+            ),
+            String::new(),
+            formatmc!(
+                c,
+                "{'c bold}Memory{'_ dc}  RSS {'_}{}{'dc}  peak {'_}{}",
+                get_mem_string(
+                    state.rss.last().map(|r| r.1).unwrap_or_default()
+                ),
+                get_mem_string(state.peak_rss),
+            ),
+        ];
+        lines.extend(
+            chart(&rss, chart_width, CHART_HEIGHT)
+                .into_iter()
+                .map(|l| formatmc!(c, " {'c}{l}{'_}")),
+        );
+
+        lines.push(String::new());
+        lines.push(formatmc!(c, "{'m bold}CPU{'_}"));
+        for (t, usage) in &state.threads {
+            let name: String = t.name.chars().take(15).collect();
+            let cells =
+                (usage.min(1.) * CPU_BAR_WIDTH as f64).round() as usize;
+            lines.push(formatmc!(
+                c,
+                "{'dm}{:>8} {name:<15} {'_ m}{}{'_ gr}{}{'_} {:>6.1}%",
+                t.tid,
+                "█".repeat(cells),
+                "░".repeat(CPU_BAR_WIDTH - cells),
+                usage * 100.,
+            ));
+        }
+
+        lines.push(String::new());
+        lines.push(formatmc!(
+            c,
+            "{'g bold}I/O{'_ dg}  read {'_}{}/s{'dg}  written {'_}{}/s",
+            get_mem_string(state.read_rate as usize),
+            get_mem_string(state.write_rate as usize),
+        ));
+
+        if !history.is_empty() {
+            let times: Vec<_> =
+                history.iter().map(|t| t.as_secs_f64()).collect();
+            let shown = times.len().min(chart_width);
+            lines.push(String::new());
+            lines.push(formatmc!(
+                c,
+                "{'y bold}Run times{'_ dy}  last {shown} runs  min {'_}{}\
+                {'dy}  max {'_}{}",
+                get_dur_string(*history.iter().min().unwrap()),
+                get_dur_string(*history.iter().max().unwrap()),
+            ));
+            lines.extend(
+                chart(&times, chart_width, CHART_HEIGHT)
+                    .into_iter()
+                    .map(|l| formatmc!(c, " {'y}{l}{'_}")),
+            );
+        }
+
+        lines.truncate(height.saturating_sub(1));
+        lines.resize(height.saturating_sub(1), String::new());
+        lines.push(formatmc!(
+            c,
+            "{'w bold}q{'_ dm} quit after this run  {'_ w bold}k{'_ dm} kill \
+            and quit{'_}"
+        ));
+
+        let mut frame = String::new();
+        for (i, l) in lines.iter().enumerate() {
+            frame += &move_to!(1, i + 1);
+            frame += l;
+            frame += codes::ERASE_TO_LN_END;
+        }
+        eprint!("{frame}");
+        _ = io::stderr().flush();
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        eprint!(
+            "{}{}",
+            codes::SHOW_CURSOR,
+            codes::DISABLE_ALTERNATIVE_BUFFER
+        );
+        _ = io::stderr().flush();
+        _ = disable_raw_mode();
+    }
+}
+
+impl RunState {
+    fn update(
+        &mut self,
+        s: ProcSample,
+        elapsed: Duration,
+        ticks_per_sec: f64,
+    ) {
+        let now = Instant::now();
+        self.rss.push((elapsed, s.rss));
+        self.peak_rss = self.peak_rss.max(s.rss);
+
+        if let Some((t, prev)) = &self.last {
+            let dt = (now - *t).as_secs_f64().max(f64::EPSILON);
+            let prev_ticks: HashMap<_, _> =
+                prev.threads.iter().map(|t| (t.tid, t.cpu_ticks)).collect();
+            self.threads = s
+                .threads
+                .iter()
+                .map(|t| {
+                    let prev = prev_ticks.get(&t.tid).copied().unwrap_or(0);
+                    let used = t.cpu_ticks.saturating_sub(prev) as f64;
+                    (t.clone(), used / ticks_per_sec / dt)
+                })
+                .collect();
+            self.read_rate = s.read.saturating_sub(prev.read) as f64 / dt;
+            self.write_rate =
+                s.written.saturating_sub(prev.written) as f64 / dt;
+        }
+
+        self.last = Some((now, s));
+    }
+}
+
+/// Draw chart of the last values that fit to the width. Returns the rows of
+/// the chart from the top.
+fn chart(values: &[f64], width: usize, height: usize) -> Vec<String> {
+    let values = &values[values.len().saturating_sub(width)..];
+    let max = values.iter().copied().fold(0., f64::max);
+    let eights: Vec<_> = values
+        .iter()
+        .map(|v| {
+            if max == 0. {
+                0
+            } else {
+                (v / max * (height * 8) as f64).round() as usize
+            }
+        })
+        .collect();
+
+    (0..height)
+        .rev()
+        .map(|row| {
+            eights
+                .iter()
+                .map(|e| CHART_PARTS[e.saturating_sub(row * 8).min(8)])
+                .collect()
+        })
+        .collect()
+}